@@ -0,0 +1,230 @@
+use std::fmt::Display;
+
+use crate::{
+    builder::RegexBuilder,
+    lexer,
+    parser::{Look, Node},
+    regex::Regex,
+};
+
+// 文字列のパターンを経由せずに正規表現を組み立てるための AST
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ast(Node);
+
+impl Ast {
+    /// 空文字列に一致する
+    pub fn empty() -> Self {
+        Self(Node::Empty)
+    }
+
+    /// 文字 `c` そのものに一致する (メタ文字もエスケープせずに指定できる)
+    pub fn literal(c: char) -> Self {
+        Self(Node::Char(c))
+    }
+
+    /// 任意の1文字に一致する (`.`)
+    pub fn any() -> Self {
+        Self(Node::Any)
+    }
+
+    /// 文字列の先頭 (`\A`)
+    pub fn start_text() -> Self {
+        Self(Node::Look(Look::StartText))
+    }
+
+    /// 文字列の終端 (`\z`)
+    pub fn end_text() -> Self {
+        Self(Node::Look(Look::EndText))
+    }
+
+    /// `parts` の連接。空の場合は空文字列に一致する
+    pub fn seq(parts: impl IntoIterator<Item = Ast>) -> Self {
+        let mut items: Vec<Node> = parts.into_iter().map(|a| a.0).collect();
+
+        match items.len() {
+            0 => Self::empty(),
+            1 => Self(items.remove(0)),
+            _ => Self(Node::Concat(items)),
+        }
+    }
+
+    /// `parts` のいずれか。空の場合は `seq` と同じく空文字列に一致する
+    pub fn alt(parts: impl IntoIterator<Item = Ast>) -> Self {
+        let mut branches: Vec<Node> = parts.into_iter().map(|a| a.0).collect();
+
+        match branches.len() {
+            0 => Self::empty(),
+            1 => Self(branches.remove(0)),
+            _ => Self(Node::Alt(branches)),
+        }
+    }
+
+    /// 0回以上の繰り返し (`*`)
+    pub fn star(inner: Ast) -> Self {
+        Self(Node::Repeat(Box::new(inner.0)))
+    }
+
+    /// 1回以上の繰り返し (`+`)
+    pub fn plus(inner: Ast) -> Self {
+        Self(Node::Plus(Box::new(inner.0)))
+    }
+
+    /// 0回または1回 (`?`)
+    pub fn optional(inner: Ast) -> Self {
+        Self(Node::Optional(Box::new(inner.0)))
+    }
+
+    /// キャプチャするグループ (`(...)`)
+    pub fn group(inner: Ast) -> Self {
+        Self(Node::Group(Box::new(inner.0), None))
+    }
+}
+
+impl Display for Ast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// 空文字列 (Empty) を除き、出力したパターンを解析すると元と同じ AST になる
+// (Empty は何も出力しないため、選択の分岐では空の分岐、括弧の中では空の括弧になるが、連接の中では消え、パターン全体では空のパターンになる)
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => Ok(()),
+            Self::Char(c) => {
+                if lexer::is_meta(*c) {
+                    write!(f, "\\{c}")
+                } else {
+                    write!(f, "{c}")
+                }
+            }
+            Self::Any => write!(f, "."),
+            Self::Look(Look::StartText) => write!(f, "\\A"),
+            Self::Look(Look::EndText) => write!(f, "\\z"),
+            Self::Concat(items) => {
+                for n in items {
+                    Self::write_wrapped(f, n, matches!(n, Self::Alt(_) | Self::Concat(_)))?;
+                }
+
+                Ok(())
+            }
+            Self::Alt(branches) => {
+                for (i, b) in branches.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    Self::write_wrapped(f, b, matches!(b, Self::Alt(_)))?;
+                }
+
+                Ok(())
+            }
+            Self::Repeat(n) => Self::write_postfix(f, n, '*'),
+            Self::Plus(n) => Self::write_postfix(f, n, '+'),
+            Self::Optional(n) => Self::write_postfix(f, n, '?'),
+            Self::Group(n, None) => write!(f, "({n})"),
+            Self::Group(n, Some(name)) => write!(f, "(?<{name}>{n})"),
+        }
+    }
+}
+
+impl Node {
+    // 結合の強さのために括弧が必要な部分を、グループを増やさないよう `(?:...)` で囲む
+    fn write_wrapped(f: &mut std::fmt::Formatter<'_>, n: &Node, wrap: bool) -> std::fmt::Result {
+        if wrap {
+            write!(f, "(?:{n})")
+        } else {
+            write!(f, "{n}")
+        }
+    }
+
+    fn write_postfix(f: &mut std::fmt::Formatter<'_>, n: &Node, op: char) -> std::fmt::Result {
+        let atomic = matches!(
+            n,
+            Self::Char(_) | Self::Any | Self::Look(_) | Self::Group(..)
+        );
+
+        Self::write_wrapped(f, n, !atomic)?;
+
+        write!(f, "{op}")
+    }
+}
+
+impl Regex {
+    pub fn from_ast(ast: Ast) -> Self {
+        Self::from_node(ast.0, &RegexBuilder::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Ast, lexer, parser::Node, regex::Regex};
+
+    #[test]
+    fn build_ast() {
+        // a(b|c)*
+        let ast = Ast::seq([
+            Ast::literal('a'),
+            Ast::star(Ast::group(Ast::alt([Ast::literal('b'), Ast::literal('c')]))),
+        ]);
+
+        assert_eq!(ast.to_string(), "a(b|c)*");
+
+        let built = Regex::from_ast(ast);
+        let parsed = Regex::new("a(b|c)*").unwrap();
+
+        for s in ["a", "ab", "ac", "acbbc", "", "b", "bcb", "abd"] {
+            assert_eq!(built.is_full_match(s), parsed.is_full_match(s));
+        }
+
+        assert_eq!(
+            format!("{built:?}"),
+            r#"Regex { pattern: "a(b|c)*", nfa_states: 11 }"#
+        );
+    }
+
+    #[test]
+    fn display_reparses_to_same_ast() {
+        let star = Ast::seq([Ast::literal('*'), Ast::literal('a')]);
+        assert_eq!(star.to_string(), r"\*a");
+
+        let asts = [
+            star,
+            Ast::alt([Ast::literal('('), Ast::literal('|'), Ast::literal('\\')]),
+            // 入れ子の連接・選択は `(?:...)` で区切りを残す
+            Ast::seq([
+                Ast::literal('a'),
+                Ast::seq([Ast::literal('b'), Ast::literal('c')]),
+            ]),
+            Ast::alt([
+                Ast::alt([Ast::literal('a'), Ast::literal('b')]),
+                Ast::literal('c'),
+            ]),
+            Ast::star(Ast::plus(Ast::seq([Ast::start_text(), Ast::any()]))),
+            Ast::optional(Ast::group(Ast::literal('{'))),
+            // 空文字列を囲む括弧は空の括弧になる
+            Ast::group(Ast::empty()),
+            Ast::star(Ast::seq([])),
+            Ast::optional(Ast::alt([])),
+        ];
+
+        for ast in asts {
+            let reparsed = Node::parse(&lexer::tokenize(&ast.to_string()).unwrap()).unwrap();
+
+            assert_eq!(Ast(reparsed), ast, "{ast}");
+        }
+    }
+
+    #[test]
+    fn build_ast_literal_metachars() {
+        let regex = Regex::from_ast(Ast::seq([
+            Ast::literal('*'),
+            Ast::optional(Ast::seq([Ast::literal('a'), Ast::literal('b')])),
+        ]));
+
+        assert!(regex.is_full_match("*"));
+        assert!(regex.is_full_match("*ab"));
+        assert!(!regex.is_full_match("*a"));
+        assert!(Regex::from_ast(Ast::seq([])).is_full_match(""));
+    }
+}
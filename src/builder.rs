@@ -0,0 +1,277 @@
+#[cfg(feature = "unicode-normalization")]
+use crate::normalize::NormalizationForm;
+use crate::regex::{Regex, RegexParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchored {
+    Yes, // 入力全体に一致するか
+    No,  // 入力の一部に一致するか
+}
+
+// 同じ位置から始まる一致が複数あるときに、`find` などがどれを選ぶか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchKind {
+    #[default]
+    LeftmostLongest, // 最も長い一致 (POSIX と同じ)
+    LeftmostFirst, // `|` で前に書いた分岐、繰り返しではより多く繰り返す方を優先した一致 (Perl と同じ)
+}
+
+const DEFAULT_SIZE_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone)]
+pub struct RegexBuilder {
+    optimize: bool,
+    anchored: Option<Anchored>,
+    captures: bool,
+    size_limit: usize,
+    match_kind: MatchKind,
+    concat_marker: Option<char>,
+    whitespace_literals: bool,
+    case_insensitive: bool,
+    #[cfg(feature = "unicode-normalization")]
+    normalization: Option<NormalizationForm>,
+}
+
+// `Regex::new` と同じ設定
+impl Default for RegexBuilder {
+    fn default() -> Self {
+        Self {
+            optimize: false,
+            anchored: None,
+            captures: false,
+            size_limit: DEFAULT_SIZE_LIMIT,
+            match_kind: MatchKind::default(),
+            concat_marker: None,
+            whitespace_literals: true,
+            case_insensitive: false,
+            #[cfg(feature = "unicode-normalization")]
+            normalization: None,
+        }
+    }
+}
+
+impl RegexBuilder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// `abc|abd` を `ab(c|d)` とするような、一致する言語を変えない AST の最適化を行うかを指定する
+    pub fn optimize(mut self, yes: bool) -> Self {
+        self.optimize = yes;
+
+        self
+    }
+
+    /// `Regex::is_match` (と非推奨の `Regex::matches`) を、入力全体への一致 (`Anchored::Yes`)
+    /// または入力の一部への一致 (`Anchored::No`) のどちらで判定するかを指定する
+    /// 指定しない場合、`matches` は全体への一致、`is_match` は一部への一致を判定する
+    /// `Regex::is_full_match` はこの指定に関わらず常に全体への一致を判定する
+    ///
+    /// パターン中の `\A` / `\z` (`^` / `$`) はこの指定に関わらず常に文字列の先頭・終端を表すため、
+    /// `Anchored::No` でも `\Aabc\z` は入力全体が `abc` の場合にのみ一致する
+    pub fn anchored_default(mut self, anchored: Anchored) -> Self {
+        self.anchored = Some(anchored);
+
+        self
+    }
+
+    /// `(...)` をキャプチャするグループとして扱うかを指定する (既定では扱わない)
+    ///
+    /// 他の多くの正規表現エンジンと異なり、指定しない場合 `(...)` は `(?:...)` と同じく
+    /// 括弧としてのみ働き、`Regex::captures` などはグループ 0 (一致全体) のみを返す
+    /// `replace_all` の `$1` などもこの指定が無い場合は空文字列になる
+    pub fn captures(mut self, yes: bool) -> Self {
+        self.captures = yes;
+
+        self
+    }
+
+    /// パターンを AST にしたときのノード数の上限を指定する (既定は 100,000)
+    ///
+    /// `a{1000}` のような回数指定は `a` を 1000 個並べたものに展開してから NFA にするため、
+    /// 上限を大きくすると大きな回数指定を書ける代わりに、その分の NFA の構築時間とメモリを消費する
+    /// 上限を超えるパターンは `RegexParseError::TooLarge` になり、上限は 4,194,304 より大きくはできない
+    pub fn size_limit(mut self, limit: usize) -> Self {
+        self.size_limit = limit;
+
+        self
+    }
+
+    /// `find` や `find_iter` などが、同じ位置から始まる一致のうちどれを返すかを指定する (既定は最も長い一致)
+    ///
+    /// `MatchKind::LeftmostFirst` では `a|ab` は `"ab"` の `a` のみに一致し、`ab|a` は `ab` に一致する
+    /// 入力全体への一致や、一致するかどうかの判定には影響しない
+    pub fn match_kind(mut self, kind: MatchKind) -> Self {
+        self.match_kind = kind;
+
+        self
+    }
+
+    /// `marker` を明示的な連接の記号とする (既定では無く、連接は要素を並べて書くのみ)
+    ///
+    /// `concat_marker('·')` では `a·b` は `ab` と同じく `a` の後に `b` が続くことを表し、
+    /// `(a|b)·c*` のように自動生成したパターンの区切りを明確にするために使える。記号を使わない連接も引き続き書ける
+    /// 記号の前後には必ず要素が必要で、`a·` や `a·|b` はエラーになる
+    ///
+    /// エスケープされていない `marker` は、その文字の元の意味に関わらず常に連接の記号になる
+    /// そのため `.` などのメタ文字を指定すると `.` は任意の1文字を表さなくなり、`\.` は `.` そのものに一致する
+    /// 通常は `·` のようにパターンに現れない文字を指定する。`\` は指定してもエスケープとして扱われる
+    /// また `Regex::escape` はこの指定を考慮せず、`marker` をエスケープしない
+    pub fn concat_marker(mut self, marker: char) -> Self {
+        self.concat_marker = Some(marker);
+
+        self
+    }
+
+    /// パターン中にエスケープせずに書いた制御文字と空白文字 (` ` を除く) をリテラルとして認めるかを指定する (既定では認める)
+    ///
+    /// 偽を指定すると、設定ファイルの書式の誤りなどで紛れ込んだタブや改行を `RegexParseError::UnescapedWhitespace` にする
+    /// その場合タブや改行に一致させるには `\t` / `\n` などのエスケープで書く (`\` の後にタブなどを書いてもよい)
+    pub fn allow_whitespace_literals(mut self, yes: bool) -> Self {
+        self.whitespace_literals = yes;
+
+        self
+    }
+
+    /// パターン全体で大文字と小文字を区別しないかを指定する (既定では区別する)
+    ///
+    /// パターン全体を `(?i:...)` で囲んだものと同じで、`(?-i:...)` の中では区別する
+    /// 各リテラルを大文字・小文字の選択に置き換えてから NFA にするため、一致の判定時に入力の文字を変換することはなく、
+    /// DFA も最小化すれば大文字と小文字を区別する場合と同じ状態数になる
+    /// `Regex::matches_ci_ascii` と異なり、`é` と `É` のような ASCII 以外の文字も区別しない
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+
+        self
+    }
+
+    /// パターン中のリテラルと入力を、一致を判定する前に Unicode の正規化形式 `form` に正規化する (既定では正規化しない)
+    ///
+    /// 合成済みの `é` と `e` + 結合用アキュートアクセント (U+0301) のような、表現が異なるだけの文字列を同じものとして扱う
+    /// `is_full_match`・`is_match`・`validate`・`partial_match` など、一致したかのみを返すメソッドは入力を正規化して判定する
+    /// 正規化した入力での位置は元の入力での位置と対応しないため、`find` や `captures` などの位置を返すメソッドは
+    /// `Regex::normalize` で正規化済みの入力のみを受け付け、正規化で変わる入力を渡すと panic する
+    /// 入力を少しずつ与える `matcher` は正規化できないため panic する
+    /// 入力を毎回正規化するため、判定のたびに入力の長さに比例する時間が加わる
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalize_input(mut self, form: NormalizationForm) -> Self {
+        self.normalization = Some(form);
+
+        self
+    }
+
+    pub fn build_from(&self, re: &str) -> Result<Regex, RegexParseError> {
+        Regex::build(re, self)
+    }
+
+    pub(crate) fn is_optimize(&self) -> bool {
+        self.optimize
+    }
+
+    pub(crate) fn is_captures(&self) -> bool {
+        self.captures
+    }
+
+    pub(crate) fn max_size(&self) -> usize {
+        self.size_limit
+    }
+
+    pub(crate) fn anchored(&self) -> Option<Anchored> {
+        self.anchored
+    }
+
+    pub(crate) fn kind(&self) -> MatchKind {
+        self.match_kind
+    }
+
+    pub(crate) fn marker(&self) -> Option<char> {
+        self.concat_marker
+    }
+
+    pub(crate) fn is_whitespace_allowed(&self) -> bool {
+        self.whitespace_literals
+    }
+
+    pub(crate) fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) fn normalization(&self) -> Option<NormalizationForm> {
+        self.normalization
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::RegexBuilder,
+        regex::{Regex, RegexParseError},
+    };
+
+    #[test]
+    fn default_builder_is_regex_new() {
+        let built = RegexBuilder::default().build_from("abc").unwrap();
+        let regex = Regex::new("abc").unwrap();
+
+        for s in ["abc", "ab", "abcd", "xabc", ""] {
+            assert_eq!(built.is_full_match(s), regex.is_full_match(s));
+            assert_eq!(built.is_match(s), regex.is_match(s));
+        }
+        assert_eq!(format!("{built:?}"), format!("{regex:?}"));
+    }
+
+    #[test]
+    fn whitespace_literals() {
+        let strict = Regex::builder().allow_whitespace_literals(false);
+
+        assert!(
+            Regex::builder()
+                .allow_whitespace_literals(true)
+                .build_from("a\tb")
+                .unwrap()
+                .is_full_match("a\tb")
+        );
+        assert!(Regex::new("a\nb").is_ok()); // 既定では認める
+
+        let err = strict.build_from("ab\tc").unwrap_err();
+        assert!(matches!(err, RegexParseError::UnescapedWhitespace('\t', 2)));
+        assert!(err.to_string().contains("position 2"));
+        assert!(strict.build_from("a\r\n").is_err());
+        assert!(strict.build_from("a\u{3000}").is_err()); // 全角の空白
+
+        // エスケープと ` ` は認める
+        let regex = strict.build_from(r"a\tb c\	").unwrap();
+        assert!(regex.is_full_match("a\tb c\t"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let regex = Regex::builder()
+            .case_insensitive(true)
+            .build_from("abc")
+            .unwrap();
+        let dfa = regex.dfa();
+
+        for s in ["ABC", "abc", "AbC", "ab", "abd", "ÀBC"] {
+            assert_eq!(regex.is_full_match(s), dfa.matches(s), "{s:?}");
+        }
+        for s in ["ABC", "abc", "AbC"] {
+            assert!(dfa.matches(s), "{s:?}");
+        }
+
+        // 大文字の遷移が加わるのみで、最小化した DFA の状態数は区別する場合と変わらない
+        let sensitive = Regex::new("abc").unwrap();
+        assert_eq!(
+            dfa.minimize().num_states(),
+            sensitive.dfa().minimize().num_states()
+        );
+
+        let regex = Regex::builder()
+            .case_insensitive(true)
+            .build_from("a(?-i:b)é")
+            .unwrap();
+        assert!(regex.is_full_match("AbÉ"));
+        assert!(!regex.is_full_match("ABé"));
+    }
+}
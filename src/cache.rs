@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+
+use crate::{
+    lru::{self, Lru},
+    regex::Regex,
+};
+
+// 入力ごとの `is_full_match` の結果と、キャッシュから結果を返した回数
+#[derive(Debug)]
+struct ResultCache {
+    entries: Lru<bool>,
+    hits: usize,
+}
+
+/// 同じ入力に対する `is_full_match` の結果をキャッシュする `Regex`
+///
+/// 入力の文字列を最大 `capacity` 件複製して保持するため、その分のメモリを消費する
+/// 同じ入力が繰り返し判定される場合にのみ効果がある
+#[derive(Debug)]
+pub struct CachedRegex {
+    regex: Regex,
+    cache: Mutex<ResultCache>,
+}
+
+impl CachedRegex {
+    /// 入力全体がパターンに一致するかを返す (`Regex::is_full_match` と同じ)
+    pub fn is_full_match(&self, input: &str) -> bool {
+        let mut cache = lru::lock(&self.cache);
+
+        if let Some(result) = cache.entries.get(input).copied() {
+            cache.hits += 1;
+            return result;
+        }
+
+        let result = self.regex.is_full_match(input);
+        cache.entries.insert(input, result);
+
+        result
+    }
+
+    #[deprecated(note = "use `is_full_match`")]
+    pub fn matches(&self, input: &str) -> bool {
+        self.is_full_match(input)
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// キャッシュから結果を返した回数
+    pub fn cache_hits(&self) -> usize {
+        lru::lock(&self.cache).hits
+    }
+}
+
+impl Regex {
+    pub fn with_result_cache(self, capacity: usize) -> CachedRegex {
+        CachedRegex {
+            regex: self,
+            cache: Mutex::new(ResultCache {
+                entries: Lru::new(capacity),
+                hits: 0,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn cached_matches_agree() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let cached = regex.clone().with_result_cache(2);
+
+        for s in ["ab", "ab", "b", "ab", "acbc", "b"] {
+            assert_eq!(cached.is_full_match(s), regex.is_full_match(s));
+        }
+
+        // 2回目・4回目の "ab" のみキャッシュから返る ("b" は "acbc" の追加時に追い出される)
+        assert_eq!(cached.cache_hits(), 2);
+    }
+
+    #[test]
+    fn zero_capacity_never_caches() {
+        let cached = Regex::new("ab").unwrap().with_result_cache(0);
+
+        assert!(cached.is_full_match("ab"));
+        assert!(cached.is_full_match("ab"));
+        assert_eq!(cached.cache_hits(), 0);
+    }
+}
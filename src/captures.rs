@@ -0,0 +1,154 @@
+use std::ops::Range;
+
+/// 入力中の一致した範囲。範囲の両端は入力でのバイトオフセットで、常に文字の境界にある
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    haystack: &'h str,
+    start: usize,
+    end: usize,
+}
+
+impl<'h> Match<'h> {
+    pub(crate) fn new(haystack: &'h str, range: Range<usize>) -> Self {
+        Self {
+            haystack,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// 一致の開始位置のバイトオフセット
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// 一致の終了位置 (一致した部分の直後) のバイトオフセット
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// 一致した部分の文字列
+    pub fn as_str(&self) -> &'h str {
+        &self.haystack[self.range()]
+    }
+
+    /// 一致した部分のバイト長
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures<'h> {
+    haystack: &'h str,
+    groups: Vec<Option<Range<usize>>>,
+}
+
+impl<'h> Captures<'h> {
+    pub(crate) fn new(haystack: &'h str, groups: Vec<Option<Range<usize>>>) -> Self {
+        Self { haystack, groups }
+    }
+
+    // グループ 0 は一致全体、一致に関与しなかったグループは None
+    pub fn get(&self, i: usize) -> Option<Match<'h>> {
+        let range = self.groups.get(i).cloned().flatten()?;
+
+        Some(Match::new(self.haystack, range))
+    }
+
+    // グループ i に一致した文字列。範囲は get と同じ
+    pub fn text(&self, i: usize) -> Option<&'h str> {
+        self.get(i).map(|m| m.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // replacement 中の `$n` と `${n}` をグループ n の文字列に置き換えて dst に追加する
+    // 存在しないグループは空文字列になり、`$$` は `$` そのものを表す
+    pub fn expand(&self, replacement: &str, dst: &mut String) {
+        let mut rest = replacement;
+
+        while let Some(i) = rest.find('$') {
+            dst.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+
+            if let Some(r) = rest.strip_prefix('$') {
+                dst.push('$');
+                rest = r;
+            } else if let Some((group, r)) = Self::parse_group_ref(rest) {
+                if let Some(text) = self.text(group) {
+                    dst.push_str(text);
+                }
+                rest = r;
+            } else {
+                dst.push('$');
+            }
+        }
+
+        dst.push_str(rest);
+    }
+
+    // `$` に続くグループ番号を読み取り、番号と残りの文字列を返す
+    fn parse_group_ref(s: &str) -> Option<(usize, &str)> {
+        let (digits, rest) = if let Some(r) = s.strip_prefix('{') {
+            let close = r.find('}')?;
+
+            (&r[..close], &r[close + 1..])
+        } else {
+            let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+            (&s[..len], &s[len..])
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        // 桁数が大きすぎる番号のグループは存在しないため空文字列として扱う
+        Some((digits.parse().unwrap_or(usize::MAX), rest))
+    }
+}
+
+// 元の文字列の寿命に縛られない、各グループに一致した文字列の所有権を持つキャプチャ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedCaptures {
+    groups: Vec<Option<String>>,
+}
+
+impl From<Captures<'_>> for OwnedCaptures {
+    fn from(value: Captures<'_>) -> Self {
+        let groups = (0..value.len())
+            .map(|i| value.text(i).map(str::to_string))
+            .collect();
+
+        Self { groups }
+    }
+}
+
+impl OwnedCaptures {
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.groups.get(i)?.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
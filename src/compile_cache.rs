@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::{
+    lru::{self, Lru},
+    regex::{Regex, RegexParseError},
+};
+
+// 保持するコンパイル済みのパターンの最大数
+const CAPACITY: usize = 64;
+
+// パターンごとのコンパイル結果
+static CACHE: OnceLock<Mutex<Lru<Arc<Regex>>>> = OnceLock::new();
+
+impl Regex {
+    /// `Regex::new` と同じだが、同じパターンのコンパイル結果をプロセス全体で共有する
+    ///
+    /// キャッシュは `Mutex` で保護されており、複数のスレッドから同時に呼び出せる
+    /// 最近使われたものから最大 64 件のパターンを保持し、それを超えると最も長く使われていないものを追い出す
+    /// コンパイルに失敗したパターンはキャッシュしない
+    pub fn new_cached(pattern: &str) -> Result<Arc<Self>, RegexParseError> {
+        let cache = CACHE.get_or_init(|| Mutex::new(Lru::new(CAPACITY)));
+
+        if let Some(regex) = lru::lock(cache).get(pattern) {
+            return Ok(Arc::clone(regex));
+        }
+
+        // コンパイル中はロックを保持しない。同時に同じパターンをコンパイルした場合は後の結果が残る
+        let regex = Arc::new(Self::new(pattern)?);
+        lru::lock(cache).insert(pattern, Arc::clone(&regex));
+
+        Ok(regex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::regex::Regex;
+
+    #[test]
+    fn new_cached_shares_compiled_regex() {
+        let r1 = Regex::new_cached("(new_cached)+").unwrap();
+        let r2 = Regex::new_cached("(new_cached)+").unwrap();
+
+        assert!(Arc::ptr_eq(&r1, &r2));
+        assert!(r1.is_full_match("new_cachednew_cached"));
+
+        let other = Regex::new_cached("new_cached?").unwrap();
+        assert!(!Arc::ptr_eq(&r1, &other));
+
+        assert!(Regex::new_cached("(").is_err());
+    }
+}
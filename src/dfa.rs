@@ -0,0 +1,1475 @@
+// internals 機能が無効な場合、外部に公開される DFA の API は crate 内から使われない
+#![cfg_attr(not(feature = "internals"), allow(dead_code))]
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::{Display, Write},
+};
+
+use crate::{
+    nfa::{Nfa, NfaState, NfaTrans},
+    parser::{Look, Node},
+    regex::Regex,
+};
+
+struct Env {
+    count: u32,
+    state_map: HashMap<BTreeSet<NfaState>, DfaState>,
+    states: HashMap<DfaState, HashMap<DfaTrans, DfaState>>,
+}
+
+impl Env {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            state_map: HashMap::new(),
+            states: HashMap::new(),
+        }
+    }
+
+    fn next(&mut self) -> DfaState {
+        self.count += 1;
+
+        DfaState(self.count)
+    }
+
+    // NFA の状態集合に対応する DFA の状態を返す。新しい集合の場合は true も返す
+    fn state_of(&mut self, nfa_states: &BTreeSet<NfaState>) -> (DfaState, bool) {
+        if let Some(s) = self.state_map.get(nfa_states) {
+            (*s, false)
+        } else {
+            let s = self.next();
+            self.state_map.insert(nfa_states.clone(), s);
+
+            (s, true)
+        }
+    }
+
+    fn into_dfa_states(self) -> HashMap<DfaState, HashMap<DfaTrans, DfaState>> {
+        self.states
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DfaState(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DfaTrans {
+    Char(char),
+    Other, // アルファベットに含まれないすべての文字
+}
+
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    start: DfaState,
+    states: HashMap<DfaState, HashMap<DfaTrans, DfaState>>,
+    accepts: HashSet<DfaState>,
+    alphabet: BTreeSet<char>,
+    classes: BTreeMap<char, char>, // アルファベットの文字 -> その文字の同値類の代表 (遷移はこの文字のみが持つ)
+}
+
+// Dfa::serialize の形式の先頭と版
+const MAGIC: &[u8; 4] = b"YRDF";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    UnexpectedEnd,
+    InvalidChar(u32),
+    InvalidTransKind(u8),
+    UnknownState(u32), // 状態の一覧に無い状態が使われている
+    TrailingBytes,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "failed to decode DFA, not a serialized DFA"),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "failed to decode DFA, unsupported format version {v}")
+            }
+            Self::UnexpectedEnd => write!(f, "failed to decode DFA, unexpected end of bytes"),
+            Self::InvalidChar(c) => write!(f, "failed to decode DFA, invalid char {c:#x}"),
+            Self::InvalidTransKind(k) => {
+                write!(f, "failed to decode DFA, invalid transition kind {k}")
+            }
+            Self::UnknownState(s) => write!(f, "failed to decode DFA, unknown state {s}"),
+            Self::TrailingBytes => write!(f, "failed to decode DFA, trailing bytes found"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// 先頭から順に値を読み出す
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], DecodeError> {
+        if self.bytes.len() < n {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn char(&mut self) -> Result<char, DecodeError> {
+        let c = self.u32()?;
+
+        char::from_u32(c).ok_or(DecodeError::InvalidChar(c))
+    }
+}
+
+impl From<&Nfa> for Dfa {
+    // 部分集合構成法により NFA を DFA に変換する
+    // 空集合に対応する状態 (これ以上受理しない状態) は作らず、遷移も持たせない
+    // 遷移はすべての状態で遷移先が等しい文字の同値類ごとに持つ
+    fn from(nfa: &Nfa) -> Self {
+        let mut env = Env::new();
+        let mut accepts = HashSet::new();
+
+        let alphabet: BTreeSet<char> = nfa
+            .states()
+            .values()
+            .flat_map(|transs| transs.keys())
+            .filter_map(|t| match t {
+                NfaTrans::Char(c) => Some(*c),
+                NfaTrans::Epsilon | NfaTrans::Any | NfaTrans::Look(_) => None,
+            })
+            .collect();
+
+        // アルファベットに含まれない文字は `.` の遷移でしか進めないため、まとめて1つの遷移とする
+        let transs: Vec<(DfaTrans, NfaTrans)> = alphabet
+            .iter()
+            .map(|c| (DfaTrans::Char(*c), NfaTrans::Char(*c)))
+            .chain(std::iter::once((DfaTrans::Other, NfaTrans::Any)))
+            .collect();
+
+        let starts: BTreeSet<NfaState> = nfa.start_states(true).into_iter().collect();
+        let (start, _) = env.state_of(&starts);
+
+        let mut stack = vec![(start, starts)];
+
+        while let Some((state, nfa_states)) = stack.pop() {
+            let current = nfa_states.iter().copied().collect();
+
+            // 入力の終端で受理する状態を受理状態とする (`\z` はここでのみ通過できる)
+            if nfa.is_accepting(&current, state == start, true) {
+                accepts.insert(state);
+            }
+
+            let mut dfa_states = HashMap::new();
+
+            for (dfa_trans, nfa_trans) in transs.iter() {
+                let nexts: BTreeSet<NfaState> =
+                    nfa.states_next(&current, nfa_trans).into_iter().collect();
+
+                if nexts.is_empty() {
+                    continue;
+                }
+
+                let (next, is_new) = env.state_of(&nexts);
+                dfa_states.insert(*dfa_trans, next);
+
+                if is_new {
+                    stack.push((next, nexts));
+                }
+            }
+
+            env.states.insert(state, dfa_states);
+        }
+
+        let mut dfa = Self::with_classes(start, env.into_dfa_states(), accepts, alphabet);
+        dfa.prune_unreachable();
+
+        dfa
+    }
+}
+
+impl From<&Regex> for Dfa {
+    fn from(value: &Regex) -> Self {
+        Self::from(value.nfa())
+    }
+}
+
+impl Nfa {
+    // 部分集合構成法を入力に現れた遷移についてのみ行い、入力全体に一致するかと作った DFA の状態の数を返す
+    // 同じ状態集合は1つの状態にまとめるため、繰り返し現れる集合の分は状態が増えない
+    pub(crate) fn determinize_partial(&self, input: &str) -> (bool, usize) {
+        let mut env = Env::new();
+
+        let starts: BTreeSet<NfaState> = self.start_states(true).into_iter().collect();
+        env.state_of(&starts);
+        let mut current = starts;
+
+        for c in input.chars() {
+            let states = current.iter().copied().collect();
+            let nexts: BTreeSet<NfaState> = self
+                .states_next(&states, &NfaTrans::Char(c))
+                .into_iter()
+                .collect();
+
+            if nexts.is_empty() {
+                return (false, env.state_map.len());
+            }
+
+            env.state_of(&nexts);
+            current = nexts;
+        }
+
+        let states = current.into_iter().collect();
+
+        (
+            self.is_accepting(&states, input.is_empty(), true),
+            env.state_map.len(),
+        )
+    }
+}
+
+impl Dfa {
+    // 遷移を文字ごとに持つ状態から DFA を作る
+    // すべての状態で遷移先が等しいアルファベットの文字を1つの同値類にまとめ、同値類の最小の文字 (代表) の遷移のみを残す
+    fn with_classes(
+        start: DfaState,
+        mut states: HashMap<DfaState, HashMap<DfaTrans, DfaState>>,
+        accepts: HashSet<DfaState>,
+        alphabet: BTreeSet<char>,
+    ) -> Self {
+        let mut order: Vec<DfaState> = states.keys().copied().collect();
+        order.sort();
+
+        // 各状態での遷移先の列 -> 同値類の代表。文字を小さい順に見るため、最初に見つかった文字が最小になる
+        let mut reps: HashMap<Vec<Option<DfaState>>, char> = HashMap::new();
+        let classes: BTreeMap<char, char> = alphabet
+            .iter()
+            .map(|c| {
+                let targets = order
+                    .iter()
+                    .map(|s| states[s].get(&DfaTrans::Char(*c)).copied())
+                    .collect();
+
+                (*c, *reps.entry(targets).or_insert(*c))
+            })
+            .collect();
+
+        for transs in states.values_mut() {
+            transs.retain(|t, _| match t {
+                DfaTrans::Char(c) => classes.get(c) == Some(c),
+                DfaTrans::Other => true,
+            });
+        }
+
+        Self {
+            start,
+            states,
+            accepts,
+            alphabet,
+            classes,
+        }
+    }
+
+    // 文字を、その文字が属する同値類の遷移の種類にする (アルファベットに含まれない文字は Other)
+    fn class_of(&self, c: char) -> DfaTrans {
+        self.classes
+            .get(&c)
+            .map_or(DfaTrans::Other, |rep| DfaTrans::Char(*rep))
+    }
+
+    // 状態の遷移を、同値類にまとめる前の文字ごと (アルファベットの文字の順で Other は最後) に返す
+    fn transitions_of(&self, state: &DfaState) -> impl Iterator<Item = (DfaTrans, DfaState)> {
+        self.alphabet
+            .iter()
+            .map(|c| DfaTrans::Char(*c))
+            .chain(std::iter::once(DfaTrans::Other))
+            .filter_map(move |t| Some((t, self.step(state, t)?)))
+    }
+
+    // state から live に含まれる状態へ遷移するアルファベットの文字と、アルファベット以外の文字でそう遷移するかを返す
+    pub(crate) fn live_transitions(
+        &self,
+        state: &DfaState,
+        live: &HashSet<DfaState>,
+    ) -> (BTreeSet<char>, bool) {
+        let mut chars = BTreeSet::new();
+        let mut other = false;
+
+        for (t, next) in self.transitions_of(state) {
+            match t {
+                _ if !live.contains(&next) => {}
+                DfaTrans::Char(c) => {
+                    chars.insert(c);
+                }
+                DfaTrans::Other => other = true,
+            }
+        }
+
+        (chars, other)
+    }
+
+    pub(crate) fn start(&self) -> DfaState {
+        self.start
+    }
+
+    pub(crate) fn is_accept(&self, state: &DfaState) -> bool {
+        self.accepts.contains(state)
+    }
+
+    pub fn next(&self, state: &DfaState, c: char) -> Option<DfaState> {
+        self.states.get(state)?.get(&self.class_of(c)).copied()
+    }
+
+    // 遷移として明示的に区別される文字の集合
+    pub fn alphabet(&self) -> &BTreeSet<char> {
+        &self.alphabet
+    }
+
+    // 遷移を区別する文字の同値類の数 (すべての状態で遷移先が等しいアルファベットの文字の組と、それ以外のすべての文字をまとめた Other)
+    // `.` のように多くの文字に一致する遷移は Other の1つになるため、一致する文字の数に依存しない
+    pub fn num_classes(&self) -> usize {
+        self.classes.iter().filter(|(c, rep)| c == rep).count() + 1
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn num_transitions(&self) -> usize {
+        self.states.values().map(|transs| transs.len()).sum()
+    }
+
+    pub fn matches(&self, input: &str) -> bool {
+        let mut state = self.start;
+
+        for c in input.chars() {
+            match self.next(&state, c) {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+
+        self.accepts.contains(&state)
+    }
+
+    // `Regex::matches_try_chars` と同じく、文字の列全体を受理するかを返す。`Err` は一致しないことが確定するまで返す
+    pub(crate) fn matches_try_chars<E>(
+        &self,
+        chars: impl IntoIterator<Item = Result<char, E>>,
+    ) -> Result<bool, E> {
+        let mut state = self.start;
+
+        for c in chars {
+            match self.next(&state, c?) {
+                Some(next) => state = next,
+                None => return Ok(false),
+            }
+        }
+
+        Ok(self.accepts.contains(&state))
+    }
+
+    // ASCII の英字の大文字と小文字を区別せずに入力全体を受理するかを返す
+    // 英字では両方の遷移をたどるため、状態の集合で判定する
+    pub(crate) fn matches_ci_ascii(&self, input: &str) -> bool {
+        let mut states = HashSet::from([self.start]);
+
+        for c in input.chars() {
+            states = states
+                .iter()
+                .flat_map(|s| {
+                    let cs = if c.is_ascii_alphabetic() {
+                        vec![c.to_ascii_lowercase(), c.to_ascii_uppercase()]
+                    } else {
+                        vec![c]
+                    };
+
+                    cs.into_iter().filter_map(|c| self.next(s, c))
+                })
+                .collect();
+
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        states.iter().any(|s| self.accepts.contains(s))
+    }
+
+    // haystack[at..] の先頭から受理する最長の部分の終了位置を返す
+    pub(crate) fn longest_match_at(&self, haystack: &str, at: usize) -> Option<usize> {
+        self.longest_prefix(&haystack[at..]).0.map(|len| at + len)
+    }
+
+    // 入力の先頭から受理する最長の部分の長さ (バイト数) と、入力をすべて読んだ後の状態に遷移が残っているかを返す
+    // 入力の途中で遷移先が無くなった場合は後者は偽になる
+    pub(crate) fn longest_prefix(&self, input: &str) -> (Option<usize>, bool) {
+        let mut state = self.start;
+        let mut longest = self.accepts.contains(&state).then_some(0);
+
+        for (i, c) in input.char_indices() {
+            match self.next(&state, c) {
+                Some(next) => state = next,
+                None => return (longest, false),
+            }
+
+            if self.accepts.contains(&state) {
+                longest = Some(i + c.len_utf8());
+            }
+        }
+
+        (
+            longest,
+            self.states.get(&state).is_some_and(|t| !t.is_empty()),
+        )
+    }
+
+    // `Nfa::first_chars` と同じく、受理する文字列の最初の文字になりうる文字の集合
+    // 空文字列を受理する場合と、アルファベット以外の文字で始まりうる場合は None
+    pub(crate) fn first_chars(&self) -> Option<BTreeSet<char>> {
+        if self.accepts.contains(&self.start) {
+            return None;
+        }
+
+        let transs = self.states.get(&self.start)?;
+        if transs.contains_key(&DfaTrans::Other) {
+            return None;
+        }
+
+        Some(
+            self.alphabet
+                .iter()
+                .copied()
+                .filter(|c| transs.contains_key(&self.class_of(*c)))
+                .collect(),
+        )
+    }
+
+    // 受理する文字列の長さ (文字数) の最小値と最大値。最大値が無い場合は None
+    // 受理する文字列が無い場合は (0, Some(0)) とする
+    pub(crate) fn len_bounds(&self) -> (usize, Option<usize>) {
+        let useful = self.useful_states();
+
+        if !useful.contains(&self.start) {
+            return (0, Some(0));
+        }
+
+        // 開始状態からの最短の距離を幅優先で求め、受理状態までの最小値をとる
+        let mut dists = HashMap::from([(self.start, 0)]);
+        let mut queue = VecDeque::from([self.start]);
+        while let Some(s) = queue.pop_front() {
+            for next in self.states[&s].values().filter(|n| useful.contains(n)) {
+                if !dists.contains_key(next) {
+                    dists.insert(*next, dists[&s] + 1);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        let min = self.accepts.iter().filter_map(|s| dists.get(s)).min();
+
+        if !self.is_finite() {
+            return (min.copied().unwrap_or(0), None);
+        }
+
+        // 閉路が無いため、深さ優先探索の帰りがけ (トポロジカル順の逆順) に、遷移先の状態から受理状態までの最長の距離を求める
+        // 状態数が多くても再帰しないように、探索はスタックで行う
+        let mut longest: HashMap<DfaState, usize> = HashMap::new();
+        let mut stack = vec![(self.start, false)];
+
+        while let Some((s, done)) = stack.pop() {
+            let nexts = self.states[&s].values().filter(|n| useful.contains(n));
+
+            if done {
+                let n = nexts.map(|n| longest[n] + 1).max().unwrap_or(0);
+                longest.insert(s, n);
+            } else if !longest.contains_key(&s) {
+                stack.push((s, true));
+                stack.extend(
+                    nexts
+                        .filter(|n| !longest.contains_key(n))
+                        .map(|n| (*n, false)),
+                );
+            }
+        }
+
+        let max = longest[&self.start];
+
+        (min.copied().unwrap_or(0), Some(max))
+    }
+
+    // 入力の各文字を読んだ後の状態を順に返す。遷移先が無くなった (一致しないことが確定した) 後は None が続く
+    pub fn walk(&self, input: &str) -> Vec<Option<DfaState>> {
+        let mut state = Some(self.start);
+
+        input
+            .chars()
+            .map(|c| {
+                state = state.and_then(|s| self.next(&s, c));
+
+                state
+            })
+            .collect()
+    }
+
+    // self の言語が other の言語に含まれるかを判定する
+    // self と other の補集合との積オートマトンで受理状態に到達できるかを、和集合のアルファベット上で探索する
+    // other 側の None は遷移先の無い状態 (補集合では受理状態) を表す
+    pub fn is_subset_of(&self, other: &Dfa) -> bool {
+        let alphabet: BTreeSet<char> = self.alphabet.union(&other.alphabet).copied().collect();
+        // どちらのアルファベットにも含まれない文字の代表 (どちらの DFA でも Other として遷移する)
+        let other_char = (0..=char::MAX as u32)
+            .filter_map(char::from_u32)
+            .find(|c| !alphabet.contains(c));
+
+        let start = (self.start, Some(other.start));
+        let mut visited = HashSet::new();
+        visited.insert(start);
+        let mut stack = vec![start];
+
+        while let Some((s1, s2)) = stack.pop() {
+            let other_accepts = s2.is_some_and(|s2| other.accepts.contains(&s2));
+
+            if self.accepts.contains(&s1) && !other_accepts {
+                return false;
+            }
+
+            for c in alphabet.iter().chain(other_char.iter()) {
+                // self 側が遷移できない場合、その先で self が受理することはない
+                let Some(n1) = self.next(&s1, *c) else {
+                    continue;
+                };
+                let n2 = s2.and_then(|s2| other.next(&s2, *c));
+
+                if visited.insert((n1, n2)) {
+                    stack.push((n1, n2));
+                }
+            }
+        }
+
+        true
+    }
+
+    // 文字はその同値類 (アルファベットに含まれない文字は Other) として、遷移の種類 t で遷移する
+    fn step(&self, state: &DfaState, t: DfaTrans) -> Option<DfaState> {
+        let t = match t {
+            DfaTrans::Char(c) => self.class_of(c),
+            DfaTrans::Other => DfaTrans::Other,
+        };
+
+        self.states.get(state)?.get(&t).copied()
+    }
+
+    // self の言語から other の言語を除いた言語を受理する DFA を返す
+    // is_subset_of と同じく self と other の補集合との積オートマトンを和集合のアルファベット上で作る
+    pub fn difference(&self, other: &Dfa) -> Dfa {
+        self.product(other, |a, b| a && !b)
+    }
+
+    // self と other の両方の言語に含まれる文字列のみを受理する DFA を返す
+    pub fn intersection(&self, other: &Dfa) -> Dfa {
+        self.product(other, |a, b| a && b)
+    }
+
+    // self と other の状態の組を状態とする積オートマトンを和集合のアルファベット上で作る
+    // other 側が遷移できない場合は None とし、組の受理は accept に (self が受理するか, other が受理するか) を渡して決める
+    fn product(&self, other: &Dfa, accept: fn(bool, bool) -> bool) -> Dfa {
+        let alphabet: BTreeSet<char> = self.alphabet.union(&other.alphabet).copied().collect();
+        let transs: Vec<DfaTrans> = alphabet
+            .iter()
+            .map(|c| DfaTrans::Char(*c))
+            .chain(std::iter::once(DfaTrans::Other))
+            .collect();
+
+        let start = (self.start, Some(other.start));
+        let mut ids: HashMap<(DfaState, Option<DfaState>), DfaState> =
+            HashMap::from([(start, DfaState(1))]);
+        let mut queue = VecDeque::from([start]);
+        let mut states = HashMap::new();
+        let mut accepts = HashSet::new();
+
+        while let Some((s1, s2)) = queue.pop_front() {
+            let id = ids[&(s1, s2)];
+
+            if accept(
+                self.accepts.contains(&s1),
+                s2.is_some_and(|s2| other.accepts.contains(&s2)),
+            ) {
+                accepts.insert(id);
+            }
+
+            let mut dfa_states = HashMap::new();
+
+            for t in transs.iter() {
+                // self 側が遷移できない場合、その先で self が受理することはない
+                let Some(n1) = self.step(&s1, *t) else {
+                    continue;
+                };
+                let n2 = s2.and_then(|s2| other.step(&s2, *t));
+                // other 側が遷移できず、その先で other が受理しなくても受理しえない場合は辿らない
+                if n2.is_none() && !accept(true, false) {
+                    continue;
+                }
+
+                let next = DfaState(ids.len() as u32 + 1);
+                let to = *ids.entry((n1, n2)).or_insert_with(|| {
+                    queue.push_back((n1, n2));
+
+                    next
+                });
+
+                dfa_states.insert(*t, to);
+            }
+
+            states.insert(id, dfa_states);
+        }
+
+        Self::with_classes(DfaState(1), states, accepts, alphabet)
+    }
+
+    // 次の形式のバイト列にする (整数はすべて u32 のリトルエンディアン)
+    // 遷移は同値類にまとめる前の文字ごとに書き出し、読み込むときに改めてまとめる
+    // "YRDF", 版 (u8), 開始状態, 状態数と状態の一覧, アルファベットの文字数と文字の一覧,
+    // 遷移数と遷移 (遷移元, 種類 (u8, 0: 文字 1: その他), 文字 (その他の場合は 0), 遷移先) の一覧, 受理状態数と受理状態の一覧
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+
+        let mut push = |n: u32| bytes.extend(n.to_le_bytes());
+
+        push(self.start.0);
+
+        let mut states: Vec<&DfaState> = self.states.keys().collect();
+        states.sort();
+        push(states.len() as u32);
+        states.iter().for_each(|s| push(s.0));
+
+        push(self.alphabet.len() as u32);
+        self.alphabet.iter().for_each(|c| push(*c as u32));
+
+        let mut transs: Vec<(DfaState, DfaTrans, DfaState)> = self
+            .states
+            .keys()
+            .flat_map(|from| self.transitions_of(from).map(|(t, to)| (*from, t, to)))
+            .collect();
+        transs.sort();
+        push(transs.len() as u32);
+
+        let mut trans_bytes = Vec::new();
+        for (from, t, to) in transs {
+            trans_bytes.extend(from.0.to_le_bytes());
+            match t {
+                DfaTrans::Char(c) => {
+                    trans_bytes.push(0);
+                    trans_bytes.extend((c as u32).to_le_bytes());
+                }
+                DfaTrans::Other => {
+                    trans_bytes.push(1);
+                    trans_bytes.extend(0u32.to_le_bytes());
+                }
+            }
+            trans_bytes.extend(to.0.to_le_bytes());
+        }
+
+        let mut accepts: Vec<&DfaState> = self.accepts.iter().collect();
+        accepts.sort();
+
+        bytes.extend(trans_bytes);
+        bytes.extend((accepts.len() as u32).to_le_bytes());
+        accepts.iter().for_each(|s| bytes.extend(s.0.to_le_bytes()));
+
+        bytes
+    }
+
+    // `from_state,input_char,to_state,is_accept` の見出しの行と、遷移ごとの行からなる CSV にする
+    // 行は遷移元・文字 (アルファベット以外の文字は最後)・遷移先の順に並べ、is_accept は遷移先が受理状態かを表す
+    // アルファベット以外の文字による遷移の input_char は空にし、`,` や `"`、改行は `"` で囲む
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("from_state,input_char,to_state,is_accept\n");
+
+        let mut transs: Vec<(DfaState, DfaTrans, DfaState)> = self
+            .states
+            .keys()
+            .flat_map(|from| self.transitions_of(from).map(|(t, to)| (*from, t, to)))
+            .collect();
+        transs.sort();
+
+        for (from, t, to) in transs {
+            let input = match t {
+                DfaTrans::Char(c @ (',' | '"' | '\n' | '\r')) => {
+                    format!("\"{}\"", c.to_string().replace('"', "\"\""))
+                }
+                DfaTrans::Char(c) => c.to_string(),
+                DfaTrans::Other => String::new(),
+            };
+            let accept = self.accepts.contains(&to);
+
+            // String への書き込みは失敗しない
+            let _ = writeln!(out, "{},{},{},{}", from.0, input, to.0, accept);
+        }
+
+        out
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Dfa, DecodeError> {
+        let mut r = Reader { bytes };
+
+        if r.take(MAGIC.len()).map_err(|_| DecodeError::InvalidMagic)? != MAGIC {
+            return Err(DecodeError::InvalidMagic);
+        }
+
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let start = DfaState(r.u32()?);
+
+        let mut states: HashMap<DfaState, HashMap<DfaTrans, DfaState>> = HashMap::new();
+        for _ in 0..r.u32()? {
+            states.insert(DfaState(r.u32()?), HashMap::new());
+        }
+
+        let known = |s: DfaState, states: &HashMap<DfaState, _>| {
+            if states.contains_key(&s) {
+                Ok(s)
+            } else {
+                Err(DecodeError::UnknownState(s.0))
+            }
+        };
+
+        known(start, &states)?;
+
+        let mut alphabet = BTreeSet::new();
+        for _ in 0..r.u32()? {
+            alphabet.insert(r.char()?);
+        }
+
+        for _ in 0..r.u32()? {
+            let from = known(DfaState(r.u32()?), &states)?;
+            let t = match r.u8()? {
+                0 => DfaTrans::Char(r.char()?),
+                1 => {
+                    r.u32()?;
+                    DfaTrans::Other
+                }
+                k => return Err(DecodeError::InvalidTransKind(k)),
+            };
+            let to = known(DfaState(r.u32()?), &states)?;
+
+            states.entry(from).or_default().insert(t, to);
+        }
+
+        let mut accepts = HashSet::new();
+        for _ in 0..r.u32()? {
+            accepts.insert(known(DfaState(r.u32()?), &states)?);
+        }
+
+        if !r.bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+
+        Ok(Self::with_classes(start, states, accepts, alphabet))
+    }
+
+    // 開始状態から到達できる状態の集合
+    pub fn reachable_states(&self) -> HashSet<DfaState> {
+        let mut visited = HashSet::new();
+        visited.insert(self.start);
+        let mut stack = vec![self.start];
+
+        while let Some(s) = stack.pop() {
+            for next in self.states.get(&s).into_iter().flat_map(|t| t.values()) {
+                if visited.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+
+        visited
+    }
+
+    // 開始状態から到達できない状態とその遷移を取り除く
+    // 部分集合構成法は開始状態から探索するため通常は何も取り除かないが、構築の方法を変えても状態が残らないようにする
+    fn prune_unreachable(&mut self) {
+        let reachable = self.reachable_states();
+
+        self.states.retain(|s, _| reachable.contains(s));
+        self.accepts.retain(|s| reachable.contains(s));
+    }
+
+    // 同じ言語を受理する状態をまとめた最小の DFA を返す (受理状態に到達できない状態は取り除く)
+    // 状態の番号は開始状態からの幅優先探索の順 (遷移は文字の順で Other は最後) に 1 から振るため、
+    // 同じ言語とアルファベットの DFA からは、元の状態の番号や HashMap の順序に関わらず常に同じ DFA になる
+    pub fn minimize(&self) -> Dfa {
+        let useful = self.useful_states();
+
+        if !useful.contains(&self.start) {
+            return Self::with_classes(
+                DfaState(1),
+                HashMap::from([(DfaState(1), HashMap::new())]),
+                HashSet::new(),
+                self.alphabet.clone(),
+            );
+        }
+
+        let transs: Vec<DfaTrans> = self
+            .alphabet
+            .iter()
+            .map(|c| DfaTrans::Char(*c))
+            .chain(std::iter::once(DfaTrans::Other))
+            .collect();
+        let target = |s: &DfaState, t: &DfaTrans| self.step(s, *t).filter(|to| useful.contains(to));
+
+        // 状態 -> 同値類の番号。受理状態かどうかで分け、遷移先の同値類が異なる状態を分けることを変わらなくなるまで繰り返す
+        let mut class: HashMap<DfaState, usize> = useful
+            .iter()
+            .map(|s| (*s, usize::from(self.accepts.contains(s))))
+            .collect();
+        let mut num_classes = 0;
+
+        loop {
+            let mut ids: HashMap<(usize, Vec<Option<usize>>), usize> = HashMap::new();
+            let refined: HashMap<DfaState, usize> = useful
+                .iter()
+                .map(|s| {
+                    let key = (
+                        class[s],
+                        transs
+                            .iter()
+                            .map(|t| target(s, t).map(|to| class[&to]))
+                            .collect(),
+                    );
+                    let id = ids.len();
+
+                    (*s, *ids.entry(key).or_insert(id))
+                })
+                .collect();
+
+            class = refined;
+
+            if ids.len() == num_classes {
+                break;
+            }
+            num_classes = ids.len();
+        }
+
+        // 同値類ごとに、最初に見つかった状態を代表として幅優先で番号を振る
+        let mut labels: HashMap<usize, DfaState> = HashMap::new();
+        labels.insert(class[&self.start], DfaState(1));
+        let mut queue = VecDeque::from([self.start]);
+        let mut states = HashMap::new();
+        let mut accepts = HashSet::new();
+
+        while let Some(s) = queue.pop_front() {
+            let label = labels[&class[&s]];
+
+            if self.accepts.contains(&s) {
+                accepts.insert(label);
+            }
+
+            let mut dfa_states = HashMap::new();
+
+            for t in transs.iter() {
+                let Some(to) = target(&s, t) else {
+                    continue;
+                };
+
+                let next = labels.len() as u32 + 1;
+                let to_label = *labels.entry(class[&to]).or_insert_with(|| {
+                    queue.push_back(to);
+
+                    DfaState(next)
+                });
+
+                dfa_states.insert(*t, to_label);
+            }
+
+            states.insert(label, dfa_states);
+        }
+
+        Self::with_classes(DfaState(1), states, accepts, self.alphabet.clone())
+    }
+
+    // 同じ言語を受理する DFA からは常に同じ DFA を返す
+    // `minimize` はアルファベットが異なると別の DFA になるため (`a|.` と `.` など)、
+    // 最小化した後にすべての状態で Other と同じ遷移をする文字をアルファベットから除き、改めて番号を振り直す
+    pub(crate) fn canonical(&self) -> Dfa {
+        let minimized = self.minimize();
+
+        let redundant: BTreeSet<char> = minimized
+            .alphabet
+            .iter()
+            .copied()
+            .filter(|c| {
+                minimized.states.keys().all(|s| {
+                    minimized.step(s, DfaTrans::Char(*c)) == minimized.step(s, DfaTrans::Other)
+                })
+            })
+            .collect();
+
+        let states = minimized
+            .states
+            .keys()
+            .map(|s| {
+                let transs = minimized
+                    .transitions_of(s)
+                    .filter(|(t, _)| !matches!(t, DfaTrans::Char(c) if redundant.contains(c)))
+                    .collect();
+
+                (*s, transs)
+            })
+            .collect();
+        let alphabet = minimized.alphabet.difference(&redundant).copied().collect();
+
+        Self::with_classes(minimized.start, states, minimized.accepts, alphabet).minimize()
+    }
+
+    // 開始状態から到達でき、かつ受理状態に到達できる状態の集合
+    pub(crate) fn useful_states(&self) -> HashSet<DfaState> {
+        let mut reverse: HashMap<DfaState, Vec<DfaState>> = HashMap::new();
+        for (from, transs) in self.states.iter() {
+            for to in transs.values() {
+                reverse.entry(*to).or_default().push(*from);
+            }
+        }
+
+        let mut co_reachable: HashSet<DfaState> = self.accepts.clone();
+        let mut stack: Vec<DfaState> = self.accepts.iter().copied().collect();
+        while let Some(s) = stack.pop() {
+            for prev in reverse.get(&s).into_iter().flatten() {
+                if co_reachable.insert(*prev) {
+                    stack.push(*prev);
+                }
+            }
+        }
+
+        // 遷移先のある状態はすべて開始状態から到達できる
+        co_reachable
+    }
+
+    // 受理する文字列が有限個であるか (受理に関わる状態の間に閉路が無いか) を返す
+    pub fn is_finite(&self) -> bool {
+        let useful = self.useful_states();
+
+        // 0: 未訪問, 1: 探索中, 2: 探索済み
+        let mut marks: HashMap<DfaState, u8> = HashMap::new();
+        let mut stack = vec![(self.start, false)];
+
+        while let Some((s, done)) = stack.pop() {
+            if done {
+                marks.insert(s, 2);
+                continue;
+            }
+
+            match marks.get(&s) {
+                Some(2) => continue,
+                Some(_) => return false,
+                None => {}
+            }
+
+            marks.insert(s, 1);
+            stack.push((s, true));
+
+            for next in self.states[&s].values().filter(|n| useful.contains(n)) {
+                match marks.get(next) {
+                    Some(1) => return false,
+                    Some(_) => {}
+                    None => stack.push((*next, false)),
+                }
+            }
+        }
+
+        true
+    }
+
+    // 受理するすべての文字列を辞書順に返す
+    // 無限個の場合と、受理に `.` (アルファベット以外の任意の文字) の遷移が関わる場合は None
+    pub fn enumerate(&self) -> Option<Vec<String>> {
+        if !self.is_finite() {
+            return None;
+        }
+
+        let useful = self.useful_states();
+        let mut examples = Vec::new();
+        let mut stack = vec![(self.start, String::new())];
+
+        while let Some((s, prefix)) = stack.pop() {
+            if !useful.contains(&s) {
+                continue;
+            }
+
+            if self.accepts.contains(&s) {
+                examples.push(prefix.clone());
+            }
+
+            for (t, next) in self.transitions_of(&s) {
+                if !useful.contains(&next) {
+                    continue;
+                }
+
+                match t {
+                    DfaTrans::Char(c) => {
+                        let mut p = prefix.clone();
+                        p.push(c);
+                        stack.push((next, p));
+                    }
+                    DfaTrans::Other => return None,
+                }
+            }
+        }
+
+        examples.sort();
+
+        Some(examples)
+    }
+
+    // 受理する文字列のうち最も短いものを幅優先探索で求める
+    // 同じ長さの中ではアルファベットの文字を小さい順に試し、それ以外の文字は代表の1文字 (なるべく英小文字) で表す
+    pub fn shortest_example(&self) -> Option<String> {
+        let other_char = ('a'..='z')
+            .chain((0..=char::MAX as u32).filter_map(char::from_u32))
+            .find(|c| !self.alphabet.contains(c));
+
+        let mut parents: HashMap<DfaState, (DfaState, char)> = HashMap::new();
+        let mut visited = HashSet::new();
+        visited.insert(self.start);
+        let mut queue = VecDeque::from([self.start]);
+
+        while let Some(state) = queue.pop_front() {
+            if self.accepts.contains(&state) {
+                let mut example = Vec::new();
+                let mut s = state;
+
+                while let Some((parent, c)) = parents.get(&s) {
+                    example.push(*c);
+                    s = *parent;
+                }
+
+                return Some(example.into_iter().rev().collect());
+            }
+
+            for c in self.alphabet.iter().chain(other_char.iter()) {
+                if let Some(next) = self.next(&state, *c)
+                    && visited.insert(next)
+                {
+                    parents.insert(next, (state, *c));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    // 同じ言語に一致する AST を状態除去法で求める
+    // パターンでは「アルファベット以外の文字」を表せないため、Other の遷移が `.` (任意の1文字) にならない場合は None
+    pub(crate) fn to_node(&self) -> Option<Node> {
+        let dfa = self.canonical();
+
+        // 0 を新しい開始状態、状態数 + 1 を新しい受理状態とし、その間の辺のみが残るまで元の状態を除く
+        let (start, end) = (0, dfa.states.len() + 1);
+        let mut edges: BTreeMap<(usize, usize), Node> = BTreeMap::new();
+        edges.insert((start, dfa.start.0 as usize), Node::Empty);
+        for s in dfa.accepts.iter() {
+            edges.insert((s.0 as usize, end), Node::Empty);
+        }
+
+        for from in dfa.states.keys() {
+            let mut by_target: BTreeMap<DfaState, Vec<DfaTrans>> = BTreeMap::new();
+            for (t, to) in dfa.transitions_of(from) {
+                by_target.entry(to).or_default().push(t);
+            }
+
+            for (to, mut ts) in by_target {
+                let label = if ts.contains(&DfaTrans::Other) {
+                    if ts.len() != dfa.alphabet.len() + 1 {
+                        return None;
+                    }
+
+                    Node::Any
+                } else {
+                    ts.sort();
+                    ts.into_iter()
+                        .map(|t| match t {
+                            DfaTrans::Char(c) => Node::Char(c),
+                            DfaTrans::Other => unreachable!(),
+                        })
+                        .reduce(alt)?
+                };
+
+                edges.insert((from.0 as usize, to.0 as usize), label);
+            }
+        }
+
+        Some(eliminate(edges, start, end))
+    }
+}
+
+// start から end への辺のみが残るまで、その間の状態 (1 から end - 1 まで) を順に除き、残った辺のラベルを返す
+// 辺が残らない場合は、1文字読んだ後の `\A` (決して一致しない) を返す
+fn eliminate(mut edges: BTreeMap<(usize, usize), Node>, start: usize, end: usize) -> Node {
+    for q in 1..end {
+        // `(x?)*` と `()*` は `x*` と空文字列に等しい
+        let lp = edges.remove(&(q, q)).and_then(|lp| match lp {
+            Node::Empty => None,
+            Node::Optional(n) => Some(*n),
+            n => Some(n),
+        });
+        let ins: Vec<(usize, Node)> = edges
+            .extract_if(.., |(_, to), _| *to == q)
+            .map(|((from, _), n)| (from, n))
+            .collect();
+        let outs: Vec<(usize, Node)> = edges
+            .extract_if(.., |(from, _), _| *from == q)
+            .map(|((_, to), n)| (to, n))
+            .collect();
+
+        for (p, a) in ins.iter() {
+            for (r, b) in outs.iter() {
+                let mut items = vec![a.clone()];
+                items.extend(lp.clone().map(|lp| Node::Repeat(Box::new(lp))));
+                items.push(b.clone());
+                let path = concat(items);
+
+                let e = match edges.remove(&(*p, *r)) {
+                    Some(e) => alt(e, path),
+                    None => path,
+                };
+                edges.insert((*p, *r), e);
+            }
+        }
+    }
+
+    edges
+        .remove(&(start, end))
+        .unwrap_or_else(|| Node::Concat(vec![Node::Any, Node::Look(Look::StartText)]))
+}
+
+// 空文字列を除いて連接する
+fn concat(items: Vec<Node>) -> Node {
+    let mut flat = Vec::new();
+    for n in items {
+        match n {
+            Node::Empty => {}
+            Node::Concat(ns) => flat.extend(ns),
+            n => flat.push(n),
+        }
+    }
+
+    match flat.len() {
+        0 => Node::Empty,
+        1 => flat.remove(0),
+        _ => Node::Concat(flat),
+    }
+}
+
+// 同じ分岐をまとめて選択にする。空文字列の分岐は `?` にする (`a|` より読みやすく、他の分岐とまとめられるため)
+fn alt(a: Node, b: Node) -> Node {
+    let mut branches: Vec<Node> = Vec::new();
+    let mut optional = false;
+
+    for n in [a, b] {
+        let ns = match n {
+            Node::Alt(ns) => ns,
+            Node::Optional(n) => {
+                optional = true;
+                vec![*n]
+            }
+            n => vec![n],
+        };
+
+        for n in ns {
+            if n == Node::Empty {
+                optional = true;
+            } else if !branches.contains(&n) {
+                branches.push(n);
+            }
+        }
+    }
+
+    let node = match branches.len() {
+        0 => return Node::Empty,
+        1 => branches.remove(0),
+        _ => Node::Alt(branches),
+    };
+
+    if optional {
+        Node::Optional(Box::new(node))
+    } else {
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dfa::{DecodeError, Dfa},
+        lexer,
+        nfa::Nfa,
+        parser::Node,
+    };
+
+    fn dfa(re: &str) -> Dfa {
+        let ast = Node::parse(&lexer::tokenize(re).unwrap()).unwrap();
+
+        Dfa::from(&Nfa::from(ast))
+    }
+
+    #[test]
+    fn dfa_works() {
+        let dfa = dfa("a(b|c)*");
+
+        assert!(dfa.matches("a"));
+        assert!(dfa.matches("ab"));
+        assert!(dfa.matches("acbbc"));
+        assert!(!dfa.matches(""));
+        assert!(!dfa.matches("b"));
+        assert!(!dfa.matches("abd"));
+    }
+
+    #[test]
+    fn dfa_walk() {
+        let dfa = dfa("a(b|c)*");
+
+        let walk = dfa.walk("abxc");
+        assert_eq!(walk.len(), 4);
+        assert!(walk[0].is_some() && walk[1].is_some());
+        assert_eq!(walk[2..], [None, None]); // `x` で遷移先が無くなる
+        let minimized = dfa.minimize();
+        assert_eq!(minimized.walk("ab")[1], minimized.walk("ac")[1]); // 最小化すると b と c の後は同じ状態
+        assert_ne!(walk[0], walk[1]);
+
+        assert!(dfa.walk("").is_empty());
+        assert_eq!(dfa.walk("b"), vec![None]);
+    }
+
+    #[test]
+    fn dfa_text_anchors() {
+        let dfa = dfa(r"(\Aa|b)*\z");
+
+        assert!(dfa.matches(""));
+        assert!(dfa.matches("abb"));
+        assert!(!dfa.matches("ba"));
+    }
+
+    #[test]
+    fn dfa_any_char() {
+        let any = dfa("a.c|abd");
+
+        assert!(any.matches("abc"));
+        assert!(any.matches("axc"));
+        assert!(any.matches("abd"));
+        assert!(!any.matches("axd"));
+        assert!(!any.matches("ac"));
+
+        assert!(dfa("abc").is_subset_of(&dfa("a.c")));
+        assert!(!dfa("a.c").is_subset_of(&dfa("abc")));
+        assert!(dfa("a.*").is_subset_of(&dfa("(a|b).*")));
+    }
+
+    #[test]
+    fn dfa_len_bounds() {
+        assert_eq!(dfa("a(b|c)?d").len_bounds(), (2, Some(3)));
+        assert_eq!(dfa("ab|(c|d)e(f|g)").len_bounds(), (2, Some(3)));
+        assert_eq!(dfa("ab*").len_bounds(), (1, None));
+        assert_eq!(dfa(r"a\Ab").len_bounds(), (0, Some(0)));
+
+        // 長い鎖でも再帰せずに求める
+        let long = dfa("a{20000}");
+        let bounds = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || long.len_bounds())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(bounds, (20000, Some(20000)));
+    }
+
+    #[test]
+    fn dfa_size() {
+        let a_bc = dfa("a(b|c)*");
+
+        // 開始 --a--> a の後 --b,c--> {b の後, c の後} で、b の後・c の後からはそれぞれ b,c で互いに遷移する
+        assert_eq!(a_bc.num_states(), 4);
+        assert_eq!(a_bc.num_transitions(), 7);
+
+        let abc = dfa("abc");
+        assert_eq!(abc.num_states(), 4);
+        assert_eq!(abc.num_transitions(), 3);
+    }
+
+    #[test]
+    fn determinize_partial_works() {
+        let nfa = Nfa::from(Node::parse(&lexer::tokenize("a(b|c)*").unwrap()).unwrap());
+        let full = Dfa::from(&nfa).num_states();
+
+        assert_eq!(nfa.determinize_partial("abc"), (true, 4));
+        assert_eq!(nfa.determinize_partial("abd"), (false, 3));
+
+        // 入力に現れない c の後の状態は作らない
+        let (matched, states) = nfa.determinize_partial("abbb");
+        assert!(matched);
+        assert!(states < full);
+
+        for s in ["", "a", "acbbc", "b", "abca"] {
+            assert_eq!(nfa.determinize_partial(s).0, Dfa::from(&nfa).matches(s));
+        }
+    }
+
+    #[test]
+    fn minimize_is_canonical() {
+        let a_bc = dfa("a(b|c)*");
+        let minimized = a_bc.minimize();
+
+        // 最小化すると b と c はすべての状態で同じ遷移をするため、1つの同値類の遷移になる
+        assert_eq!(minimized.num_states(), 2);
+        assert_eq!(minimized.num_transitions(), 2);
+        assert_eq!(minimized.num_classes(), 3);
+        for s in ["a", "abcb", "", "b", "abd"] {
+            assert_eq!(minimized.matches(s), a_bc.matches(s));
+        }
+
+        // 同じ DFA を2回最小化しても、同じ言語の別の DFA を最小化しても同じバイト列になる
+        assert_eq!(a_bc.minimize().serialize(), a_bc.minimize().serialize());
+        assert_eq!(
+            dfa("ab|cb").minimize().serialize(),
+            dfa("(a|c)b").minimize().serialize()
+        );
+        assert_eq!(
+            dfa("x.*|y").minimize().serialize(),
+            dfa("y|x(.|y)*").minimize().serialize()
+        );
+
+        // 何にも一致しない
+        let never = dfa("a\\Ab").minimize();
+        assert_eq!(never.num_states(), 1);
+        assert!(!never.matches("ab"));
+    }
+
+    #[test]
+    fn dfa_has_no_unreachable_states() {
+        for re in ["a(b|c)*", r"(\Aa|b)*\z", "a.c|abd", "(ab|a)(c|bc)", "x*"] {
+            let dfa = dfa(re);
+
+            assert_eq!(dfa.reachable_states().len(), dfa.num_states(), "{re}");
+        }
+    }
+
+    #[test]
+    fn dfa_classes() {
+        // `.` は文字ごとではなく Other の1つの遷移になる
+        assert_eq!(dfa(".+").num_classes(), 1);
+        assert_eq!(dfa(".+").num_transitions(), 2);
+        assert_eq!(dfa("a.*").num_classes(), 2);
+
+        // すべての状態で同じ遷移をする文字は1つの同値類になり、遷移の数は文字の数に依存しない
+        let letters: Vec<String> = ('a'..='z').map(String::from).collect();
+        let (few, many) = (
+            dfa("(a|b|c)+").minimize(),
+            dfa(&format!("({})+", letters.join("|"))).minimize(),
+        );
+        assert_eq!(few.num_classes(), 2);
+        assert_eq!(many.num_classes(), 2);
+        assert_eq!(few.num_transitions(), many.num_transitions());
+        assert!(letters.iter().all(|s| many.matches(&s.repeat(3))));
+        assert!(!many.matches("aA"));
+        assert_eq!(
+            Dfa::deserialize(&many.serialize()).unwrap().num_classes(),
+            2
+        );
+
+        let dfa = dfa("x.+");
+        assert!(dfa.matches("xあ"));
+        assert!(dfa.matches("x\u{10ffff}a"));
+        assert!(!dfa.matches("x"));
+    }
+
+    #[test]
+    fn dfa_serialize_round_trip() {
+        let original = dfa("a(b|c)*.|x");
+        let bytes = original.serialize();
+        let decoded = Dfa::deserialize(&bytes).unwrap();
+
+        for s in ["a", "ab", "abz", "acbbcx", "x", "", "b", "xx"] {
+            assert_eq!(decoded.matches(s), original.matches(s), "{s}");
+        }
+        assert_eq!(decoded.serialize(), bytes);
+
+        assert_eq!(
+            Dfa::deserialize(&bytes[..bytes.len() - 1]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        assert_eq!(
+            Dfa::deserialize(b"abc").unwrap_err(),
+            DecodeError::InvalidMagic
+        );
+
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert_eq!(
+            Dfa::deserialize(&newer).unwrap_err(),
+            DecodeError::UnsupportedVersion(2)
+        );
+    }
+
+    #[test]
+    fn dfa_to_csv() {
+        let dfa = dfa("ab|,").minimize();
+        let csv = dfa.to_csv();
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("from_state,input_char,to_state,is_accept")
+        );
+
+        let rows: Vec<&str> = lines.collect();
+        assert_eq!(rows.len(), dfa.num_transitions());
+
+        let start = dfa.start;
+        let after_a = dfa.next(&start, 'a').unwrap();
+        let accept = dfa.next(&after_a, 'b').unwrap();
+        for row in [
+            format!("{},a,{},false", start.0, after_a.0),
+            format!("{},\",\",{},true", start.0, accept.0),
+            format!("{},b,{},true", after_a.0, accept.0),
+        ] {
+            assert!(rows.contains(&row.as_str()), "{row}");
+        }
+
+        // 同じ DFA からは常に同じ CSV になる
+        assert_eq!(dfa.to_csv(), csv);
+
+        // アルファベット以外の文字による遷移は input_char が空になる
+        let any = self::dfa("a.");
+        let after_a = any.next(&any.start, 'a').unwrap();
+        let accept = any.next(&after_a, 'x').unwrap();
+        let row = format!("{},,{},true", after_a.0, accept.0);
+        assert!(any.to_csv().lines().any(|l| l == row), "{row}");
+    }
+
+    #[test]
+    fn dfa_subset() {
+        assert!(dfa("ab").is_subset_of(&dfa("a(b|c)*")));
+        assert!(dfa("a(b|c)*").is_subset_of(&dfa("a(b|c)*")));
+        assert!(!dfa("a(b|c)*").is_subset_of(&dfa("ab")));
+        assert!(!dfa("a*").is_subset_of(&dfa("aa*"))); // 空文字は aa* に含まれない
+        assert!(!dfa("ab").is_subset_of(&dfa("a")));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use crate::{
+    nfa::{NfaBfs, NfaTrans},
+    parser::Look,
+    regex::Regex,
+};
+
+impl Regex {
+    /// NFA を Mermaid の `stateDiagram-v2` にする
+    /// 開始状態は `[*]` からの遷移で、受理状態は `[*]` への遷移と太枠 (`accept` クラス) で表す
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("stateDiagram-v2\n");
+        let accept = self.nfa().accept();
+
+        // String への書き込みは失敗しない
+        let _ = writeln!(out, "    [*] --> s{}", self.nfa().start().id());
+
+        for (state, transs) in NfaBfs::new(self.nfa()) {
+            for (t, nexts) in transs {
+                let label = match t {
+                    NfaTrans::Epsilon => "ε".to_string(),
+                    NfaTrans::Char(c) if c.is_whitespace() || c.is_control() => {
+                        c.escape_debug().to_string()
+                    }
+                    NfaTrans::Char(c) => c.to_string(),
+                    NfaTrans::Any => ".".to_string(),
+                    NfaTrans::Look(Look::StartText) => "\\A".to_string(),
+                    NfaTrans::Look(Look::EndText) => "\\z".to_string(),
+                };
+
+                for next in nexts {
+                    let _ = writeln!(out, "    s{} --> s{}: {}", state.id(), next.id(), label);
+                }
+            }
+        }
+
+        let _ = writeln!(out, "    s{} --> [*]", accept.id());
+        let _ = writeln!(out, "    classDef accept stroke-width:4px");
+        let _ = writeln!(out, "    class s{} accept", accept.id());
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn to_mermaid_works() {
+        let regex = Regex::new("a|b").unwrap();
+        let mermaid = regex.to_mermaid();
+        let nfa = regex.nfa();
+
+        assert!(mermaid.starts_with("stateDiagram-v2\n"));
+        assert!(mermaid.contains(&format!("    [*] --> s{}\n", nfa.start().id())));
+        assert!(mermaid.contains(&format!("    s{} --> [*]\n", nfa.accept().id())));
+
+        // 開始 -ε-> 各分岐 -a/b-> 各分岐の受理 -ε-> 受理 の 6 本
+        let edges: Vec<&str> = mermaid
+            .lines()
+            .filter(|l| l.contains("--> s") && !l.contains("[*]"))
+            .collect();
+        assert_eq!(edges.len(), 6);
+        assert_eq!(edges.iter().filter(|l| l.ends_with(": ε")).count(), 4);
+        assert_eq!(edges.iter().filter(|l| l.ends_with(": a")).count(), 1);
+        assert_eq!(edges.iter().filter(|l| l.ends_with(": b")).count(), 1);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    dfa::DfaState,
+    nfa::{NfaState, NfaTrans},
+    parser::{Look, Node},
+    regex::Regex,
+};
+
+/// 入力全体がパターンに一致しなかった理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonMatch {
+    /// 一致の可能性が無くなった位置 (入力の終端で受理しなかった場合は入力の長さ) のバイトオフセット
+    pub offset: usize,
+    /// その位置で受け付けられた文字の集合
+    pub expected: BTreeSet<char>,
+    /// その位置で `.` により任意の1文字を受け付けられたか
+    /// DFA から作った正規表現では、DFA のアルファベットに含まれない任意の文字を受け付けられたか
+    pub expected_any: bool,
+}
+
+/// `Regex::validate` で入力全体がパターンに一致しなかったことを表すエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    non_match: NonMatch,
+}
+
+impl ValidationError {
+    pub fn non_match(&self) -> &NonMatch {
+        &self.non_match
+    }
+
+    /// 一致の可能性が無くなった位置のバイトオフセット
+    pub fn offset(&self) -> usize {
+        self.non_match.offset
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let NonMatch {
+            offset,
+            expected,
+            expected_any,
+        } = &self.non_match;
+
+        write!(f, "input does not match at position {offset}")?;
+
+        if *expected_any {
+            write!(f, ", expected any character")
+        } else if expected.is_empty() {
+            write!(f, ", expected end of input")
+        } else {
+            write!(f, ", expected one of [")?;
+
+            for (i, c) in expected.iter().enumerate() {
+                if i == expected.len() - 1 {
+                    write!(f, "{c:?}")?;
+                } else {
+                    write!(f, "{c:?}, ")?;
+                }
+            }
+
+            write!(f, "]")
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Node {
+    // ノードの意味を英語の文で表す
+    fn describe(&self) -> String {
+        match self {
+            Self::Empty => "empty string".to_string(),
+            Self::Char(c) => format!("literal {c:?}"),
+            Self::Any => "any character".to_string(),
+            Self::Look(Look::StartText) => "start of text".to_string(),
+            Self::Look(Look::EndText) => "end of text".to_string(),
+            Self::Concat(items) => {
+                let items: Vec<String> = items.iter().map(Self::describe_nested).collect();
+
+                format!("concatenation of: {}", items.join(", then "))
+            }
+            Self::Alt(branches) => {
+                let branches: Vec<String> = branches.iter().map(Self::describe_nested).collect();
+
+                format!("either {}", branches.join(" or "))
+            }
+            Self::Repeat(n) => format!("zero-or-more of ({})", n.describe()),
+            Self::Plus(n) => format!("one-or-more of ({})", n.describe()),
+            Self::Optional(n) => format!("optional ({})", n.describe()),
+            // グループは一致する文字列を変えないため、中身のみを表す
+            Self::Group(n, _) => n.describe(),
+        }
+    }
+
+    // 連接・選択の要素の中の連接・選択は、区切りが曖昧にならないよう括弧で囲む
+    fn describe_nested(&self) -> String {
+        match self {
+            Self::Group(n, _) => n.describe_nested(),
+            Self::Concat(_) | Self::Alt(_) => format!("({})", self.describe()),
+            _ => self.describe(),
+        }
+    }
+}
+
+impl Regex {
+    /// パターンの構造を英語の文で説明する。学習やエラーの診断のためのもので、形式は変わりうる
+    pub fn explain(&self) -> String {
+        self.ast().describe()
+    }
+
+    /// 入力全体がパターンに一致すれば `Ok`、一致しなければどこで何が期待されていたかを表すエラーを返す
+    /// フォームの入力の検証などでそのままメッセージとして使うことを想定している
+    pub fn validate(&self, input: &str) -> Result<(), ValidationError> {
+        match self.explain_nonmatch(input) {
+            None => Ok(()),
+            Some(non_match) => Err(ValidationError { non_match }),
+        }
+    }
+
+    /// 入力全体がパターンに一致しない場合、どこまで一致できたかとその位置で受け付けられた文字を返す
+    /// 入力全体が一致する場合は None を返す (`anchored_default` の指定には従わない)
+    pub fn explain_nonmatch(&self, input: &str) -> Option<NonMatch> {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        if self.backend_dfa().is_some() {
+            return self.explain_nonmatch_dfa(input);
+        }
+
+        let nfa = self.nfa();
+        let mut states = nfa.start_states(true);
+        let mut offset = 0;
+
+        for (i, c) in input.char_indices() {
+            let nexts = nfa.states_next(&states, &NfaTrans::Char(c));
+
+            if nexts.is_empty() {
+                return Some(self.non_match(&states, i));
+            }
+
+            states = nexts;
+            offset = i + c.len_utf8();
+        }
+
+        if nfa.is_accepting(&states, input.is_empty(), true) {
+            None
+        } else {
+            Some(self.non_match(&states, offset))
+        }
+    }
+
+    // DFA で判定する場合は、受理状態に到達しえない状態に遷移した位置で一致の可能性が無くなったとする
+    fn explain_nonmatch_dfa(&self, input: &str) -> Option<NonMatch> {
+        let dfa = self.dfa();
+        let live = self.live_dfa_states();
+        let non_match = |state: &DfaState, offset: usize| {
+            let (expected, expected_any) = dfa.live_transitions(state, live);
+
+            NonMatch {
+                offset,
+                expected,
+                expected_any,
+            }
+        };
+
+        let mut state = dfa.start();
+        let mut offset = 0;
+
+        if !live.contains(&state) {
+            return Some(non_match(&state, 0));
+        }
+
+        for (i, c) in input.char_indices() {
+            match dfa.next(&state, c).filter(|next| live.contains(next)) {
+                Some(next) => state = next,
+                None => return Some(non_match(&state, i)),
+            }
+
+            offset = i + c.len_utf8();
+        }
+
+        if dfa.is_accept(&state) {
+            None
+        } else {
+            Some(non_match(&state, offset))
+        }
+    }
+
+    fn non_match(&self, states: &HashSet<NfaState>, offset: usize) -> NonMatch {
+        let mut expected = BTreeSet::new();
+        let mut expected_any = false;
+
+        for transs in states.iter().filter_map(|s| self.nfa().states().get(s)) {
+            for t in transs.keys() {
+                match t {
+                    NfaTrans::Char(c) => {
+                        expected.insert(*c);
+                    }
+                    NfaTrans::Any => expected_any = true,
+                    NfaTrans::Epsilon | NfaTrans::Look(_) => {}
+                }
+            }
+        }
+
+        NonMatch {
+            offset,
+            expected,
+            expected_any,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{explain::NonMatch, regex::Regex};
+
+    #[test]
+    fn explain_works() {
+        assert_eq!(
+            Regex::new("a(b|c)*").unwrap().explain(),
+            "concatenation of: literal 'a', then zero-or-more of (either literal 'b' or literal 'c')"
+        );
+        assert_eq!(
+            Regex::new(r"ab|\A.?").unwrap().explain(),
+            "either (concatenation of: literal 'a', then literal 'b') or (concatenation of: start of text, then optional (any character))"
+        );
+    }
+
+    #[test]
+    fn validate_works() {
+        let regex = Regex::new("(0|1|2|3|4|5|6|7|8|9)+").unwrap();
+
+        assert_eq!(regex.validate("123"), Ok(()));
+
+        let err = regex.validate("12a").unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert!(err.to_string().contains("position 2"));
+
+        let err = Regex::new("ab").unwrap().validate("abc").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input does not match at position 2, expected end of input"
+        );
+        assert_eq!(
+            Regex::new("ab")
+                .unwrap()
+                .validate("a")
+                .unwrap_err()
+                .to_string(),
+            "input does not match at position 1, expected one of ['b']"
+        );
+    }
+
+    #[test]
+    fn explain_nonmatch_works() {
+        let regex = Regex::new("abc").unwrap();
+
+        assert_eq!(
+            regex.explain_nonmatch("abx"),
+            Some(NonMatch {
+                offset: 2,
+                expected: BTreeSet::from(['c']),
+                expected_any: false,
+            })
+        );
+        assert_eq!(regex.explain_nonmatch("abc"), None);
+
+        // 入力が途中で終わった場合は終端の位置を返す
+        let regex = Regex::new("あ(b|c.)").unwrap();
+        assert_eq!(
+            regex.explain_nonmatch("あc"),
+            Some(NonMatch {
+                offset: 4,
+                expected: BTreeSet::new(),
+                expected_any: true,
+            })
+        );
+        assert_eq!(
+            regex.explain_nonmatch("あ").map(|n| n.expected),
+            Some(BTreeSet::from(['b', 'c']))
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    nfa::{Nfa, NfaState, NfaTrans},
+    regex::Regex,
+};
+
+// 状態ごとに、そこへ到達するのに必要な編集の最小回数
+type Costs = HashMap<NfaState, usize>;
+
+impl Regex {
+    /// 高々 `k` 回の文字の挿入・削除・置換で、入力全体がパターンに一致するようにできるかを返す
+    ///
+    /// NFA の各状態に到達するまでの最小の編集回数を1文字ずつ更新するため、
+    /// 計算量は入力の文字数・NFA の状態数・`k` の積に比例する
+    pub fn matches_within(&self, input: &str, k: usize) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        let nfa = self.nfa();
+
+        let mut costs = Costs::new();
+        costs.insert(nfa.start(), 0);
+        let mut costs = Self::close(nfa, costs, k, true);
+
+        for c in input.chars() {
+            let mut nexts = Costs::new();
+
+            for (s, cost) in costs.iter() {
+                // 入力の文字を余分なものとして読み飛ばす (挿入)
+                Self::relax(&mut nexts, *s, cost + 1, k);
+
+                for (t, targets) in nfa.states().get(s).into_iter().flatten() {
+                    let cost = match t {
+                        NfaTrans::Char(p) if *p == c => *cost,
+                        NfaTrans::Any => *cost,
+                        // 別の文字として読む (置換)
+                        NfaTrans::Char(_) => cost + 1,
+                        NfaTrans::Epsilon | NfaTrans::Look(_) => continue,
+                    };
+
+                    for target in targets {
+                        Self::relax(&mut nexts, *target, cost, k);
+                    }
+                }
+            }
+
+            if nexts.is_empty() {
+                return false;
+            }
+
+            costs = Self::close(nfa, nexts, k, false);
+        }
+
+        costs.iter().any(|(s, _)| {
+            let mut states = HashSet::new();
+            states.insert(*s);
+
+            nfa.is_accepting(&states, input.is_empty(), true)
+        })
+    }
+
+    // epsilon遷移 (編集なし) と、パターンの文字を入力に無いものとして進む遷移 (削除) で到達できる状態に広げる
+    fn close(nfa: &Nfa, mut costs: Costs, k: usize, at_start: bool) -> Costs {
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (s, cost) in costs.clone() {
+                let mut starts = HashSet::new();
+                starts.insert(s);
+
+                for e in nfa.epsilon_next(starts, at_start, false) {
+                    changed |= Self::relax(&mut costs, e, cost, k);
+                }
+
+                for (t, targets) in nfa.states().get(&s).into_iter().flatten() {
+                    if matches!(t, NfaTrans::Char(_) | NfaTrans::Any) {
+                        for target in targets {
+                            changed |= Self::relax(&mut costs, *target, cost + 1, k);
+                        }
+                    }
+                }
+            }
+        }
+
+        costs
+    }
+
+    // より少ない編集回数で到達できた場合に更新し、更新したかを返す
+    fn relax(costs: &mut Costs, s: NfaState, cost: usize, k: usize) -> bool {
+        if cost > k || costs.get(&s).is_some_and(|c| *c <= cost) {
+            return false;
+        }
+
+        costs.insert(s, cost);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn matches_within_edit_distance() {
+        let regex = Regex::new("abc").unwrap();
+
+        assert!(regex.matches_within("abx", 1)); // 置換
+        assert!(!regex.matches_within("abx", 0));
+        assert!(regex.matches_within("ab", 1)); // 削除
+        assert!(regex.matches_within("abcd", 1)); // 挿入
+        assert!(regex.matches_within("xabc", 1));
+        assert!(!regex.matches_within("axc", 0));
+        assert!(!regex.matches_within("xyz", 2));
+        assert!(regex.matches_within("xyz", 3));
+        assert!(regex.matches_within("", 3));
+
+        let regex = Regex::new("a(b|c)*d").unwrap();
+        assert!(regex.matches_within("abcbd", 0));
+        assert!(regex.matches_within("abxbd", 1));
+        assert!(!regex.matches_within("xbxbd", 1));
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    nfa::{Nfa, NfaState, NfaTrans},
+    regex::Regex,
+};
+
+impl Nfa {
+    // `.` 以外の文字の遷移のみで cluster の各文字を順に読む
+    fn cluster_next_exact(&self, states: &HashSet<NfaState>, cluster: &str) -> HashSet<NfaState> {
+        let mut states = states.clone();
+
+        for c in cluster.chars() {
+            let mut nexts = HashSet::new();
+
+            for s in states.iter() {
+                if let Some(transs) = self.states().get(s)
+                    && let Some(c_nexts) = transs.get(&NfaTrans::Char(c))
+                {
+                    nexts.extend(c_nexts);
+                }
+            }
+
+            states = self.epsilon_next(nexts, false, false);
+        }
+
+        states
+    }
+}
+
+impl Regex {
+    /// `is_full_match` と同じく入力全体への一致を判定するが、`.` が1文字ではなく書記素クラスタ1つに一致する
+    /// リテラルの文字はクラスタを構成する文字の並びとしてのみ一致し、クラスタの途中で区切られることはない
+    /// クラスタは UAX #29 の拡張書記素クラスタとする
+    /// DFA から作った正規表現 (`difference` など) では DFA から求めたパターンを使い、パターンで表せない場合は panic する
+    pub fn matches_graphemes(&self, input: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        let nfa = self.nfa();
+        let mut states = nfa.start_states(true);
+
+        for cluster in input.graphemes(true) {
+            let mut nexts = nfa.states_next(&states, &NfaTrans::Any);
+            nexts.extend(nfa.cluster_next_exact(&states, cluster));
+            states = nexts;
+
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        nfa.is_accepting(&states, input.is_empty(), true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn dot_matches_each_cluster() {
+        let dot = Regex::new(".").unwrap();
+
+        for cluster in [
+            "e\u{301}",
+            "\r\n",
+            "か\u{3099}",
+            "\u{1F469}\u{200D}\u{1F4BB}", // ZWJ で繋がれた絵文字
+            "\u{1F1EF}\u{1F1F5}",         // 地域指示記号の組 (国旗)
+            "\u{1100}\u{1161}\u{11A8}",   // ハングルの L+V+T
+            "\u{0915}\u{093F}",           // デーヴァナーガリーの कि (SpacingMark が続く)
+        ] {
+            assert!(dot.matches_graphemes(cluster), "{cluster:?}");
+        }
+
+        // 絵文字以外の間の ZWJ は直前の文字に付くのみで、次の文字とは繋がない
+        let regex = Regex::new("..").unwrap();
+        assert!(regex.matches_graphemes("a\u{200D}b"));
+        assert!(!dot.matches_graphemes("a\u{200D}b"));
+
+        let regex = Regex::new("...").unwrap();
+        assert!(regex.matches_graphemes("ae\u{301}b"));
+    }
+
+    #[test]
+    fn dot_matches_grapheme_cluster() {
+        let decomposed = "e\u{301}"; // e + 結合用アキュートアクセント
+
+        let regex = Regex::new(".").unwrap();
+        assert!(regex.matches_graphemes(decomposed));
+        assert!(!regex.is_full_match(decomposed)); // 通常は2文字として扱う
+
+        let regex = Regex::new("a.b").unwrap();
+        assert!(regex.matches_graphemes("ae\u{301}b"));
+
+        let regex = Regex::new("..").unwrap();
+        assert!(!regex.matches_graphemes(decomposed));
+
+        let regex = Regex::new("e\u{301}").unwrap();
+        assert!(regex.matches_graphemes(decomposed));
+
+        let regex = Regex::new("e.").unwrap();
+        assert!(!regex.matches_graphemes(decomposed)); // クラスタの途中では区切らない
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    builder::RegexBuilder,
+    regex::{Regex, RegexParseError},
+};
+
+impl Regex {
+    /// 2つのパターンをコンパイルし、両方の全体に一致する文字列のみに一致する正規表現を返す
+    ///
+    /// 結果は2つの DFA の積の DFA で一致を判定する、`difference` と同じ種類の正規表現で、グループを持たない
+    /// パターンを使う機能は DFA から組み立て直したパターンを使い、パターンで表せない場合は panic する
+    /// どちらかのパターンが不正な場合はそのエラーを返す
+    pub fn intersect_str(a: &str, b: &str) -> Result<Regex, RegexParseError> {
+        let (a, b) = (Regex::new(a)?, Regex::new(b)?);
+
+        Ok(Self::with_dfa(
+            a.dfa().intersection(b.dfa()),
+            &RegexBuilder::new(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Regex, RegexParseError};
+
+    #[test]
+    fn intersect_str_works() {
+        let regex = Regex::intersect_str("a.*", ".*b").unwrap();
+
+        for s in ["axb", "ab", "aab", "abxb"] {
+            assert!(regex.is_full_match(s), "{s:?}");
+        }
+        for s in ["axc", "a", "b", "xab", ""] {
+            assert!(!regex.is_full_match(s), "{s:?}");
+        }
+        assert!(regex.is_match("xaxbx"));
+        assert_eq!(regex.find("xaxbx").map(|m| m.range()), Some(1..4));
+        assert_eq!(regex.capture_count(), 0);
+
+        // 一致する文字列が無い場合は何にも一致しない
+        let regex = Regex::intersect_str("a+", "b*").unwrap();
+        assert_eq!(regex.enumerate(), Some(vec![]));
+
+        let regex = Regex::intersect_str("(a|b)(a|b)", r"\Aa.|.a").unwrap();
+        assert_eq!(
+            regex.enumerate(),
+            Some(vec!["aa".to_string(), "ab".to_string(), "ba".to_string()])
+        );
+
+        assert!(matches!(
+            Regex::intersect_str("a", "(b"),
+            Err(RegexParseError::UnexpectedEOF)
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    nfa::{NfaState, NfaTrans},
+    regex::Regex,
+};
+
+/// 入力に現れた遷移のみを DFA の状態として記憶しながら判定する (遅延 DFA)
+///
+/// 状態の数が `max_states` に達すると記憶した状態をすべて捨てて作り直すため、
+/// 使用するメモリは上限で抑えられる。`&mut self` で使うため、スレッドごとに作って使う
+#[derive(Debug)]
+pub struct LazyDfa<'r> {
+    regex: &'r Regex,
+    max_states: usize,
+    sets: Vec<BTreeSet<NfaState>>, // 状態番号 -> NFA の状態集合
+    ids: HashMap<BTreeSet<NfaState>, usize>, // NFA の状態集合 -> 状態番号
+    transitions: HashMap<(usize, char), usize>, // 遷移先が空集合になるものは記憶しない
+    clears: usize,
+}
+
+impl<'r> LazyDfa<'r> {
+    pub(crate) fn new(regex: &'r Regex, max_states: usize) -> Self {
+        Self {
+            regex,
+            max_states,
+            sets: Vec::new(),
+            ids: HashMap::new(),
+            transitions: HashMap::new(),
+            clears: 0,
+        }
+    }
+
+    /// `Regex::is_full_match` と同じく入力全体への一致を判定する
+    /// DFA から作った正規表現 (`difference` など) では状態を記憶せずにその DFA で判定する
+    pub fn matches(&mut self, input: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.regex.normalize(input);
+
+        if let Some(dfa) = self.regex.backend_dfa() {
+            return dfa.matches(input);
+        }
+
+        let nfa = self.regex.nfa();
+        let start = nfa.start_states(true);
+
+        // 現在の状態と遷移先の2つを記憶できない場合は NFA のシミュレーションのみで判定する
+        if self.max_states < 2 {
+            let mut states = start;
+
+            for c in input.chars() {
+                states = nfa.states_next(&states, &NfaTrans::Char(c));
+
+                if states.is_empty() {
+                    return false;
+                }
+            }
+
+            return nfa.is_accepting(&states, input.is_empty(), true);
+        }
+
+        let mut current = self.intern(start.into_iter().collect());
+
+        for c in input.chars() {
+            current = match self.transitions.get(&(current, c)) {
+                Some(next) => *next,
+                None => {
+                    let states: HashSet<NfaState> = self.sets[current].iter().copied().collect();
+                    let nexts = nfa.states_next(&states, &NfaTrans::Char(c));
+
+                    if nexts.is_empty() {
+                        return false;
+                    }
+
+                    let nexts: BTreeSet<NfaState> = nexts.into_iter().collect();
+
+                    if self.sets.len() >= self.max_states && !self.ids.contains_key(&nexts) {
+                        // 現在の状態だけを残して作り直す
+                        let kept = self.sets[current].clone();
+                        self.clear();
+                        current = self.intern(kept);
+                    }
+
+                    let next = self.intern(nexts);
+                    self.transitions.insert((current, c), next);
+
+                    next
+                }
+            };
+        }
+
+        let states = self.sets[current].iter().copied().collect();
+
+        nfa.is_accepting(&states, input.is_empty(), true)
+    }
+
+    /// 状態の数が上限に達して記憶した状態を捨てた回数
+    pub fn cache_clears(&self) -> usize {
+        self.clears
+    }
+
+    fn intern(&mut self, set: BTreeSet<NfaState>) -> usize {
+        if let Some(id) = self.ids.get(&set) {
+            return *id;
+        }
+
+        let id = self.sets.len();
+        self.sets.push(set.clone());
+        self.ids.insert(set, id);
+
+        id
+    }
+
+    fn clear(&mut self) {
+        self.sets.clear();
+        self.ids.clear();
+        self.transitions.clear();
+        self.clears += 1;
+    }
+}
+
+impl Regex {
+    /// 記憶する DFA の状態の数を `max_states` までに抑えた遅延 DFA を返す
+    pub fn lazy_dfa(&self, max_states: usize) -> LazyDfa<'_> {
+        LazyDfa::new(self, max_states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn lazy_dfa_agrees_with_nfa() {
+        let regex = Regex::new("a(b|c)*d|x.y").unwrap();
+        let inputs = ["ad", "abcbd", "abce", "xzy", "xy", "", "acccccd"];
+
+        let mut unbounded = regex.lazy_dfa(usize::MAX);
+        let mut tiny = regex.lazy_dfa(2);
+        let mut nfa_only = regex.lazy_dfa(0);
+
+        for _ in 0..2 {
+            for s in inputs {
+                assert_eq!(unbounded.matches(s), regex.is_full_match(s), "{s}");
+                assert_eq!(tiny.matches(s), regex.is_full_match(s), "{s}");
+                assert_eq!(nfa_only.matches(s), regex.is_full_match(s), "{s}");
+            }
+        }
+
+        assert_eq!(unbounded.cache_clears(), 0);
+        assert!(tiny.cache_clears() > 0);
+    }
+}
@@ -1,12 +1,25 @@
 use std::fmt::Display;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum TokenKind {
-    Char(char), // single character
-    LPare,      // (
-    RPare,      // )
-    Bar,        // |
-    Star,       // *
+    Char(char),   // single character
+    LPare,        // (
+    RPare,        // )
+    Bar,          // |
+    Star,         // *
+    Plus,         // +
+    Question,     // ?
+    Dot,          // .
+    LBracket,     // [
+    RBracket,     // ]
+    LBrace,       // {
+    RBrace,       // }
+    Caret,        // ^
+    Dollar,       // $
+    StartText,    // \A
+    EndText,      // \z
+    Concat(char), // ビルダーで指定した明示的な連接の記号
 }
 
 impl From<char> for TokenKind {
@@ -16,6 +29,15 @@ impl From<char> for TokenKind {
             ')' => Self::RPare,
             '|' => Self::Bar,
             '*' => Self::Star,
+            '+' => Self::Plus,
+            '?' => Self::Question,
+            '.' => Self::Dot,
+            '[' => Self::LBracket,
+            ']' => Self::RBracket,
+            '{' => Self::LBrace,
+            '}' => Self::RBrace,
+            '^' => Self::Caret,
+            '$' => Self::Dollar,
             _ => Self::Char(value),
         }
     }
@@ -29,17 +51,129 @@ impl Display for TokenKind {
             Self::RPare => write!(f, "`)`"),
             Self::Bar => write!(f, "`|`"),
             Self::Star => write!(f, "`*`"),
+            Self::Plus => write!(f, "`+`"),
+            Self::Question => write!(f, "`?`"),
+            Self::Dot => write!(f, "`.`"),
+            Self::LBracket => write!(f, "`[`"),
+            Self::RBracket => write!(f, "`]`"),
+            Self::LBrace => write!(f, "`{{`"),
+            Self::RBrace => write!(f, "`}}`"),
+            Self::Caret => write!(f, "`^`"),
+            Self::Dollar => write!(f, "`$`"),
+            Self::StartText => write!(f, "`\\A`"),
+            Self::EndText => write!(f, "`\\z`"),
+            Self::Concat(c) => write!(f, "`{c}`"),
         }
     }
 }
 
-pub fn tokenize(src: &str) -> Vec<TokenKind> {
-    src.chars().map(TokenKind::from).collect()
+// `\` でエスケープしないと文字そのものとして扱われない文字
+pub(crate) fn is_meta(c: char) -> bool {
+    "()|*+?.[]{}^$\\".contains(c)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LexError {
+    InvalidEscape(String), // `\xHH` や `\u{...}` の形式になっていない、または意味を持たない英数字のエスケープ
+    InvalidCodePoint(u32), // Unicode のスカラー値ではない `\u{...}`
+    UnescapedWhitespace(char, usize), // エスケープされていない制御文字・空白文字とそのバイトオフセット
+}
+
+// 連接の記号を指定しない字句解析 (テストでパターンから直接 AST を作るときに使う)
+#[cfg(test)]
+pub(crate) fn tokenize(src: &str) -> Result<Vec<TokenKind>, LexError> {
+    tokenize_with(src, None, true)
+}
+
+// concat_marker に指定した文字は、エスケープされていない場合にその文字の元の意味に関わらず明示的な連接の記号とする
+// allow_whitespace が偽の場合、エスケープされていない制御文字と空白文字 (` ` を除く) をエラーにする
+pub(crate) fn tokenize_with(
+    src: &str,
+    concat_marker: Option<char>,
+    allow_whitespace: bool,
+) -> Result<Vec<TokenKind>, LexError> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            // `\` に続く記号はその文字自体として扱う (末尾の `\` は `\` 自体)
+            // 英数字は以下のもの以外を誤り (`\d` など他の実装で意味を持つもの) とする
+            let token = match chars.next() {
+                Some('A') => TokenKind::StartText,
+                Some('z') => TokenKind::EndText,
+                Some('n') => TokenKind::Char('\n'),
+                Some('t') => TokenKind::Char('\t'),
+                Some('r') => TokenKind::Char('\r'),
+                Some('0') => TokenKind::Char('\0'),
+                Some('x') => TokenKind::Char(hex_escape(&mut chars)?),
+                Some('u') => TokenKind::Char(unicode_escape(&mut chars)?),
+                // 連接の記号自体はエスケープしてその文字として使える
+                Some(escaped) if escaped.is_alphanumeric() && Some(escaped) != concat_marker => {
+                    return Err(LexError::InvalidEscape(format!("\\{escaped}")));
+                }
+                Some(escaped) => TokenKind::Char(escaped),
+                None => TokenKind::Char('\\'),
+            };
+
+            tokens.push(token);
+        } else if !allow_whitespace && c != ' ' && (c.is_control() || c.is_whitespace()) {
+            let offset = src.len() - chars.as_str().len() - c.len_utf8();
+
+            return Err(LexError::UnescapedWhitespace(c, offset));
+        } else if Some(c) == concat_marker {
+            tokens.push(TokenKind::Concat(c));
+        } else {
+            tokens.push(TokenKind::from(c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// `\x` に続くちょうど2桁の16進数を読む
+fn hex_escape(chars: &mut std::str::Chars) -> Result<char, LexError> {
+    let digits: String = chars.by_ref().take(2).collect();
+
+    match u32::from_str_radix(&digits, 16) {
+        // 2桁の16進数は常に Unicode のスカラー値になる
+        // `u32::from_str_radix` は先頭の `+` を受け付けるため、16進数の文字のみであることも確かめる
+        Ok(value) if digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Ok(char::from_u32(value).unwrap())
+        }
+        _ => Err(LexError::InvalidEscape(format!("\\x{digits}"))),
+    }
+}
+
+// `\u` に続く `{...}` の中の1桁から6桁の16進数を読む
+fn unicode_escape(chars: &mut std::str::Chars) -> Result<char, LexError> {
+    let invalid = |digits: &str| LexError::InvalidEscape(format!("\\u{digits}"));
+
+    if chars.next() != Some('{') {
+        return Err(invalid(""));
+    }
+
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => digits.push(c),
+            None => return Err(invalid(&format!("{{{digits}"))),
+        }
+    }
+
+    let value = match u32::from_str_radix(&digits, 16) {
+        // `u32::from_str_radix` は先頭の `+` を受け付けるため、16進数の文字のみであることも確かめる
+        Ok(value) if digits.len() <= 6 && digits.chars().all(|c| c.is_ascii_hexdigit()) => value,
+        _ => return Err(invalid(&format!("{{{digits}}}"))),
+    };
+
+    char::from_u32(value).ok_or(LexError::InvalidCodePoint(value))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::{self, TokenKind};
+    use crate::lexer::{self, LexError, TokenKind};
 
     #[test]
     fn tokenize_raw_chars() {
@@ -55,8 +189,130 @@ mod tests {
             TokenKind::Star,
         ];
 
-        let result = lexer::tokenize(raw);
+        let result = lexer::tokenize(raw).unwrap();
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn tokenize_metachars() {
+        let expected = vec![
+            TokenKind::Plus,
+            TokenKind::Question,
+            TokenKind::Dot,
+            TokenKind::LBracket,
+            TokenKind::RBracket,
+            TokenKind::LBrace,
+            TokenKind::RBrace,
+            TokenKind::Caret,
+            TokenKind::Dollar,
+        ];
+
+        assert_eq!(lexer::tokenize("+?.[]{}^$").unwrap(), expected);
+        assert_eq!(
+            lexer::tokenize(r"\+\[").unwrap(),
+            vec![TokenKind::Char('+'), TokenKind::Char('[')]
+        );
+    }
+
+    #[test]
+    fn display_metachars() {
+        let displayed: Vec<String> = lexer::tokenize("+?.[]{}^$")
+            .unwrap()
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+
+        assert_eq!(
+            displayed,
+            vec![
+                "`+`", "`?`", "`.`", "`[`", "`]`", "`{`", "`}`", "`^`", "`$`"
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_escapes() {
+        let raw = r"\Aa\*\(\\\z";
+
+        let expected = vec![
+            TokenKind::StartText,
+            TokenKind::Char('a'),
+            TokenKind::Char('*'),
+            TokenKind::Char('('),
+            TokenKind::Char('\\'),
+            TokenKind::EndText,
+        ];
+
+        assert_eq!(lexer::tokenize(raw).unwrap(), expected);
+
+        // 意味を持たない英数字のエスケープは誤り
+        for invalid in [r"\d", r"\w", r"\B", r"\1", r"\あ"] {
+            assert_eq!(
+                lexer::tokenize(invalid),
+                Err(LexError::InvalidEscape(invalid.to_string())),
+                "{invalid}"
+            );
+        }
+        assert_eq!(
+            lexer::tokenize(r"\-\.\ \/").unwrap(),
+            vec![
+                TokenKind::Char('-'),
+                TokenKind::Char('.'),
+                TokenKind::Char(' '),
+                TokenKind::Char('/'),
+            ]
+        );
+
+        // 連接の記号は英数字でもエスケープしてその文字として使える
+        assert_eq!(
+            lexer::tokenize_with(r"a\cb", Some('c'), true).unwrap(),
+            vec![
+                TokenKind::Char('a'),
+                TokenKind::Char('c'),
+                TokenKind::Char('b'),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_char_escapes() {
+        assert_eq!(
+            lexer::tokenize(r"\n\t\r\0\x41\u{1F600}\u{3042}").unwrap(),
+            vec![
+                TokenKind::Char('\n'),
+                TokenKind::Char('\t'),
+                TokenKind::Char('\r'),
+                TokenKind::Char('\0'),
+                TokenKind::Char('A'),
+                TokenKind::Char('\u{1F600}'),
+                TokenKind::Char('あ'),
+            ]
+        );
+
+        // `\x` の後が2桁の16進数でない、`\u` の後が `{...}` でない場合はエラー
+        for invalid in [
+            r"\x4",
+            r"\xg1",
+            r"\x+1",
+            r"\u41",
+            r"\u{}",
+            r"\u{41",
+            r"\u{+41}",
+            r"\u{1234567}",
+        ] {
+            assert!(
+                matches!(lexer::tokenize(invalid), Err(LexError::InvalidEscape(_))),
+                "{invalid}"
+            );
+        }
+        assert_eq!(
+            lexer::tokenize(r"\u{110000}"),
+            Err(LexError::InvalidCodePoint(0x110000))
+        );
+        assert_eq!(
+            lexer::tokenize(r"\u{D800}"),
+            Err(LexError::InvalidCodePoint(0xD800))
+        );
+    }
 }
@@ -1,6 +1,62 @@
+pub(crate) mod ast;
+pub(crate) mod builder;
+#[cfg(feature = "result-cache")]
+pub(crate) mod cache;
+pub(crate) mod captures;
+#[cfg(feature = "compile-cache")]
+pub(crate) mod compile_cache;
+pub(crate) mod dfa;
+pub(crate) mod diagram;
+pub(crate) mod explain;
+pub(crate) mod fuzzy;
+#[cfg(feature = "graphemes")]
+pub(crate) mod grapheme;
+pub(crate) mod intersect;
+pub(crate) mod lazy;
 pub(crate) mod lexer;
+#[cfg(any(feature = "result-cache", feature = "compile-cache"))]
+pub(crate) mod lru;
+pub(crate) mod matcher;
 pub(crate) mod nfa;
+#[cfg(feature = "unicode-normalization")]
+pub(crate) mod normalize;
+pub(crate) mod optimizer;
 pub(crate) mod parser;
+pub(crate) mod pikevm;
+pub(crate) mod posix;
+#[cfg(feature = "profiling")]
+pub(crate) mod profile;
 pub(crate) mod regex;
+pub(crate) mod reverse;
+#[cfg(feature = "rand")]
+pub(crate) mod sample;
+pub(crate) mod search;
+pub(crate) mod symbol;
 
-pub use regex::Regex;
+pub use ast::Ast;
+pub use builder::{Anchored, MatchKind, RegexBuilder};
+#[cfg(feature = "result-cache")]
+pub use cache::CachedRegex;
+pub use captures::{Captures, Match, OwnedCaptures};
+#[cfg(feature = "internals")]
+pub use dfa::{DecodeError, Dfa, DfaState, DfaTrans};
+pub use explain::{NonMatch, ValidationError};
+pub use lazy::LazyDfa;
+pub use lexer::TokenKind;
+pub use matcher::{MatchStatus, Matcher, PartialMatch};
+pub use nfa::Symbol;
+#[cfg(feature = "internals")]
+pub use nfa::{NfaBfs, NfaState, NfaTrans};
+#[cfg(feature = "unicode-normalization")]
+pub use normalize::NormalizationForm;
+#[cfg(feature = "internals")]
+pub use parser::Look;
+#[cfg(feature = "profiling")]
+pub use profile::MatchStats;
+#[cfg(feature = "std")]
+pub use regex::Timeout;
+pub use regex::{FindIter, Regex, RegexParseError, TooComplex};
+#[cfg(feature = "rand")]
+pub use sample::Rng;
+pub use search::SearchOptions;
+pub use symbol::SymbolRegex;
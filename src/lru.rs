@@ -0,0 +1,80 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+// 文字列をキーとする値を、最近使われたものから最大 capacity 件まで保持する
+// 使われるたびに時刻を進めて記録し、いっぱいのときは最も古い時刻のものを追い出す
+#[derive(Debug)]
+pub(crate) struct Lru<V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (V, u64)>, // キー -> (値, 最後に使われた時刻)
+}
+
+impl<V> Lru<V> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn get(&mut self, key: &str) -> Option<&V> {
+        self.tick += 1;
+
+        let (value, used) = self.entries.get_mut(key)?;
+        *used = self.tick;
+
+        Some(value)
+    }
+
+    pub(crate) fn insert(&mut self, key: &str, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.capacity
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| k.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+
+        self.entries.insert(key.to_string(), (value, self.tick));
+    }
+}
+
+// キャッシュのロックを取る
+// 他のスレッドがキャッシュの更新中に panic した場合でもキャッシュの内容自体は壊れないため、そのまま使う
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lru;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get("a"), Some(&1));
+
+        // "b" が最も長く使われていない
+        lru.insert("c", 3);
+        assert_eq!(lru.get("b"), None);
+        assert_eq!(lru.get("a"), Some(&1));
+        assert_eq!(lru.get("c"), Some(&3));
+
+        let mut empty = Lru::new(0);
+        empty.insert("a", 1);
+        assert_eq!(empty.get("a"), None);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+
+use crate::{
+    dfa::DfaState,
+    nfa::{NfaState, NfaTrans},
+    regex::Regex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStatus {
+    Matching, // まだ受理していないが、入力が続けば受理しうる
+    Accepted, // これまでの入力全体で受理している
+    Dead,     // 以降どのような入力が来ても受理しない
+}
+
+// `Regex::partial_match` の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialMatch {
+    Full,    // 入力全体が一致する
+    Partial, // 一致しないが、入力を続ければ一致しうる (入力が一致するものの接頭辞である)
+    None,    // どのように入力を続けても一致しない
+}
+
+/// 入力を少しずつ与えながら、それまでの入力全体がパターンに一致するかを調べる
+/// `Regex::matcher` で作る
+#[derive(Debug, Clone)]
+pub struct Matcher<'r> {
+    regex: &'r Regex,
+    position: Position,
+    consumed: bool,
+}
+
+// 受理状態に到達しうる現在の状態。空 (None) になった場合は Dead
+#[derive(Debug, Clone)]
+enum Position {
+    Nfa(HashSet<NfaState>),
+    Dfa(Option<DfaState>), // DFA で判定する正規表現の場合
+}
+
+impl<'r> Matcher<'r> {
+    pub(crate) fn new(regex: &'r Regex) -> Self {
+        let position = match regex.backend_dfa() {
+            Some(dfa) => Position::Dfa(Some(dfa.start())),
+            None => Position::Nfa(regex.nfa().start_states(true)),
+        };
+        let mut matcher = Self {
+            regex,
+            position,
+            consumed: false,
+        };
+        matcher.retain_live();
+
+        matcher
+    }
+
+    // 受理状態に到達しえない状態を取り除く
+    fn retain_live(&mut self) {
+        match &mut self.position {
+            Position::Nfa(states) => {
+                let live = self.regex.live_states();
+                states.retain(|s| live.contains(s));
+            }
+            Position::Dfa(state) => {
+                let live = self.regex.live_dfa_states();
+                *state = state.filter(|s| live.contains(s));
+            }
+        }
+    }
+
+    fn is_dead(&self) -> bool {
+        match &self.position {
+            Position::Nfa(states) => states.is_empty(),
+            Position::Dfa(state) => state.is_none(),
+        }
+    }
+
+    /// 1文字を入力に加える
+    pub fn feed(&mut self, c: char) {
+        // 一度 Dead になった状態からは復帰しないため遷移を計算しない
+        if self.is_dead() {
+            return;
+        }
+
+        match &mut self.position {
+            Position::Nfa(states) => {
+                *states = self.regex.nfa().states_next(states, &NfaTrans::Char(c));
+            }
+            Position::Dfa(state) => {
+                *state = state.and_then(|s| self.regex.dfa().next(&s, c));
+            }
+        }
+        self.consumed = true;
+        self.retain_live();
+    }
+
+    /// 文字列を入力に加える。途中で Dead になった場合は残りを読まない
+    pub fn feed_str(&mut self, chunk: &str) {
+        for c in chunk.chars() {
+            self.feed(c);
+
+            if self.is_dead() {
+                return;
+            }
+        }
+    }
+
+    /// これまでの入力全体が一致するか (`Accepted`)、続きの入力によって一致しうるか (`Matching`)、
+    /// どのように入力を続けても一致しないか (`Dead`) を返す
+    pub fn status(&self) -> MatchStatus {
+        // ここで入力が終わったものとして受理を判定する
+        let accepted = match &self.position {
+            Position::Nfa(states) => self.regex.nfa().is_accepting(states, !self.consumed, true),
+            Position::Dfa(state) => state.is_some_and(|s| self.regex.dfa().is_accept(&s)),
+        };
+
+        if accepted {
+            MatchStatus::Accepted
+        } else if self.is_dead() {
+            MatchStatus::Dead
+        } else {
+            MatchStatus::Matching
+        }
+    }
+}
+
+impl Regex {
+    /// 入力中の文字列の検証のために、入力全体が一致するか、続きを入力すれば一致しうるか、どちらでもないかを返す
+    /// `matcher` に入力全体を一度に与えた場合の状態と同じ
+    /// ビルダーで `normalize_input` を指定した場合は入力を正規化してから判定し、末尾の文字が続きの入力と合成されうることは考慮しない
+    pub fn partial_match(&self, input: &str) -> PartialMatch {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        let mut matcher = Matcher::new(self);
+        matcher.feed_str(input);
+
+        match matcher.status() {
+            MatchStatus::Accepted => PartialMatch::Full,
+            MatchStatus::Matching => PartialMatch::Partial,
+            MatchStatus::Dead => PartialMatch::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        matcher::{MatchStatus, PartialMatch},
+        regex::Regex,
+    };
+
+    #[test]
+    fn partial_match_works() {
+        let regex = Regex::new("abc").unwrap();
+
+        assert_eq!(regex.partial_match("abc"), PartialMatch::Full);
+        assert_eq!(regex.partial_match("ab"), PartialMatch::Partial);
+        assert_eq!(regex.partial_match(""), PartialMatch::Partial);
+        assert_eq!(regex.partial_match("x"), PartialMatch::None);
+        assert_eq!(regex.partial_match("abcd"), PartialMatch::None);
+    }
+
+    #[test]
+    fn matcher_feed_chunks() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let mut matcher = regex.matcher();
+
+        assert_eq!(matcher.status(), MatchStatus::Matching);
+
+        matcher.feed_str("ab");
+        assert_eq!(matcher.status(), MatchStatus::Accepted);
+
+        matcher.feed_str("c");
+        assert_eq!(matcher.status(), MatchStatus::Accepted);
+
+        matcher.feed_str("a"); // (b|c)* の後に a は不可
+        assert_eq!(matcher.status(), MatchStatus::Dead);
+
+        matcher.feed_str("b"); // Dead からは復帰しない
+        assert_eq!(matcher.status(), MatchStatus::Dead);
+    }
+
+    #[test]
+    fn matcher_text_anchors() {
+        let regex = Regex::new(r"\Aab\z").unwrap();
+        let mut matcher = regex.matcher();
+
+        matcher.feed_str("ab");
+        assert_eq!(matcher.status(), MatchStatus::Accepted);
+
+        matcher.feed_str("b");
+        assert_eq!(matcher.status(), MatchStatus::Dead);
+    }
+
+    #[test]
+    fn matcher_dead_on_unreachable_accept() {
+        // "a" の後は `\z` の後に文字が必要、または `\A` を通過する必要があり、受理状態に到達できない (入力の前から Dead)
+        for pattern in [r"a\zb", r"a\Ab"] {
+            let regex = Regex::new(pattern).unwrap();
+            let mut matcher = regex.matcher();
+
+            matcher.feed_str("a");
+            assert_eq!(matcher.status(), MatchStatus::Dead, "{pattern}");
+            assert_eq!(regex.partial_match("a"), PartialMatch::None, "{pattern}");
+        }
+
+        // 先頭では `\A` を通過できる
+        let regex = Regex::new(r"\Aa|b\Ac").unwrap();
+        assert_eq!(regex.partial_match(""), PartialMatch::Partial);
+        assert_eq!(regex.partial_match("b"), PartialMatch::None);
+        assert_eq!(regex.partial_match("a"), PartialMatch::Full);
+
+        let regex = Regex::new(r"a\z|ab").unwrap();
+        assert_eq!(regex.partial_match("a"), PartialMatch::Full);
+        assert_eq!(regex.partial_match("ab"), PartialMatch::Full);
+        assert_eq!(regex.partial_match("abc"), PartialMatch::None);
+    }
+
+    #[test]
+    fn matcher_not_yet_accepted() {
+        let regex = Regex::new("abc").unwrap();
+        let mut matcher = regex.matcher();
+
+        matcher.feed_str("a");
+        assert_eq!(matcher.status(), MatchStatus::Matching);
+
+        matcher.feed_str("bc");
+        assert_eq!(matcher.status(), MatchStatus::Accepted);
+    }
+}
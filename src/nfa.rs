@@ -1,6 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
 
-use crate::parser::Node;
+use crate::parser::{Look, Node};
 
 struct Env {
     count: u32,
@@ -18,19 +21,41 @@ impl Env {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub(crate) struct NfaState(u32);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NfaState(u32);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub(crate) enum NfaTrans {
+impl NfaState {
+    pub(crate) fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+// NFA の遷移に使う記号。文字列を扱う API では char を使う
+pub trait Symbol: Eq + Hash + Clone {}
+
+impl<T: Eq + Hash + Clone> Symbol for T {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NfaTrans<S = char> {
     Epsilon,
-    Char(char),
+    Char(S),
+    Any,        // 任意の1文字
+    Look(Look), // 位置の条件が成り立つときのみ通過できる epsilon遷移
+}
+
+// Nfa::validate で見つかった構造の誤り
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NfaError {
+    MissingStart(NfaState),                          // 開始状態から遷移が無い
+    AcceptHasTransitions(NfaState),                  // 受理状態から遷移がある
+    DanglingTarget { from: NfaState, to: NfaState }, // 遷移先がどこにも定義されていない
+    Unreachable(NfaState),                           // 開始状態から到達できない
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct Nfa {
+pub(crate) struct Nfa<S = char> {
     start: NfaState,
-    states: HashMap<NfaState, HashMap<NfaTrans, HashSet<NfaState>>>,
+    states: HashMap<NfaState, HashMap<NfaTrans<S>, HashSet<NfaState>>>,
     accept: NfaState,
 }
 
@@ -45,14 +70,20 @@ impl From<Node> for Nfa {
 impl Nfa {
     fn new(n: Node, env: &mut Env) -> Self {
         match n {
-            Node::Char(c) => Self::new_char(c, env),
-            Node::Concat(n1, n2) => Self::new_concat(*n1, *n2, env),
-            Node::Or(n1, n2) => Self::new_or(*n1, *n2, env),
+            Node::Empty => Self::new_trans(NfaTrans::Epsilon, env),
+            Node::Char(c) => Self::new_trans(NfaTrans::Char(c), env),
+            Node::Any => Self::new_trans(NfaTrans::Any, env),
+            Node::Look(l) => Self::new_trans(NfaTrans::Look(l), env),
+            Node::Concat(items) => Self::new_concat(items, env),
+            Node::Alt(branches) => Self::new_alt(branches, env),
             Node::Repeat(n) => Self::new_repeat(*n, env),
+            Node::Plus(n) => Self::new_plus(*n, env),
+            Node::Optional(n) => Self::new_optional(*n, env),
+            Node::Group(n, _) => Self::new(*n, env),
         }
     }
 
-    fn new_char(c: char, env: &mut Env) -> Self {
+    fn new_trans(trans: NfaTrans, env: &mut Env) -> Self {
         let start = env.next();
 
         let mut states = HashMap::new();
@@ -60,7 +91,7 @@ impl Nfa {
         let mut accepts = HashSet::new();
         let accept = env.next();
         accepts.insert(accept);
-        start_trans.insert(NfaTrans::Char(c), accepts);
+        start_trans.insert(trans, accepts);
         states.insert(start, start_trans);
 
         Self {
@@ -70,41 +101,69 @@ impl Nfa {
         }
     }
 
-    fn new_concat(n1: Node, n2: Node, env: &mut Env) -> Self {
+    fn new_concat(items: Vec<Node>, env: &mut Env) -> Self {
         let start = env.next();
         let accept = env.next();
 
         let mut states = HashMap::new();
 
-        let nfa1 = Self::new(n1, env);
-        let nfa2 = Self::new(n2, env);
+        // start -- epsilon --> nfa1.start, nfa1.accept -- epsilon --> nfa2.start, ..., nfak.accept -- epsilon --> accept
+        let mut prev = start;
+
+        for n in items {
+            let nfa = Self::new(n, env);
 
-        // start -- epsilon --> nfa1.start
+            let mut trans = HashMap::new();
+            let mut trans_accepts = HashSet::new();
+            trans_accepts.insert(nfa.start);
+            trans.insert(NfaTrans::Epsilon, trans_accepts);
+
+            states.insert(prev, trans);
+            states.extend(nfa.states);
+
+            prev = nfa.accept;
+        }
+
+        let mut last_trans = HashMap::new();
+        let mut last_trans_accepts = HashSet::new();
+        last_trans_accepts.insert(accept);
+        last_trans.insert(NfaTrans::Epsilon, last_trans_accepts);
+
+        states.insert(prev, last_trans);
+
+        Self {
+            start,
+            states,
+            accept,
+        }
+    }
+
+    fn new_alt(branches: Vec<Node>, env: &mut Env) -> Self {
+        let start = env.next();
+        let accept = env.next();
+
+        let mut states = HashMap::new();
+
+        let nfas: Vec<Nfa> = branches.into_iter().map(|b| Self::new(b, env)).collect();
+
+        // start -- epsilon --> nfa.start (すべての分岐)
         let mut start_trans = HashMap::new();
-        let mut start_trans_accepts = HashSet::new();
-        start_trans_accepts.insert(nfa1.start);
+        let start_trans_accepts = nfas.iter().map(|nfa| nfa.start).collect();
         start_trans.insert(NfaTrans::Epsilon, start_trans_accepts);
 
         states.insert(start, start_trans);
 
-        // nfa1.accept -- epsilon --> nfa2.start
-        let mut trans = HashMap::new();
-        let mut trans_accepts = HashSet::new();
-        trans_accepts.insert(nfa2.start);
-        trans.insert(NfaTrans::Epsilon, trans_accepts);
+        for nfa in nfas {
+            // nfa.accept -- epsilon --> accept
+            let mut a_trans = HashMap::new();
+            let mut a_trans_accepts = HashSet::new();
+            a_trans_accepts.insert(accept);
+            a_trans.insert(NfaTrans::Epsilon, a_trans_accepts);
 
-        states.insert(nfa1.accept, trans);
+            states.insert(nfa.accept, a_trans);
 
-        // nfa2.accept -- epsilon --> accept
-        let mut nfa2_accept_trans = HashMap::new();
-        let mut nfa2_accept_trans_accepts = HashSet::new();
-        nfa2_accept_trans_accepts.insert(accept);
-        nfa2_accept_trans.insert(NfaTrans::Epsilon, nfa2_accept_trans_accepts);
-
-        states.insert(nfa2.accept, nfa2_accept_trans);
-
-        states.extend(nfa1.states);
-        states.extend(nfa2.states);
+            states.extend(nfa.states);
+        }
 
         Self {
             start,
@@ -113,44 +172,63 @@ impl Nfa {
         }
     }
 
-    fn new_or(n1: Node, n2: Node, env: &mut Env) -> Self {
+    fn new_repeat(n: Node, env: &mut Env) -> Self {
+        // 新しい開始状態を作らず nfa.start を繰り返しの起点とし、新しい状態は受理状態のみにする
+        // nfa.start に入る遷移は内側の繰り返しが1回を終えたときにしか無いため、そこから抜けても言語は変わらない
+        let mut nfa = Self::new(n, env);
+        let accept = env.next();
+
+        // nfa.start -- epsilon --> accept
+        nfa.states
+            .entry(nfa.start)
+            .or_default()
+            .entry(NfaTrans::Epsilon)
+            .or_default()
+            .insert(accept);
+
+        // nfa.accept -- epsilon --> nfa.start
+        let mut a_trans = HashMap::new();
+        let mut a_trans_accepts = HashSet::new();
+        a_trans_accepts.insert(nfa.start);
+        a_trans.insert(NfaTrans::Epsilon, a_trans_accepts);
+
+        nfa.states.insert(nfa.accept, a_trans);
+
+        Self {
+            start: nfa.start,
+            states: nfa.states,
+            accept,
+        }
+    }
+
+    fn new_plus(n: Node, env: &mut Env) -> Self {
         let start = env.next();
         let accept = env.next();
 
         let mut states = HashMap::new();
 
-        let nfa1 = Self::new(n1, env);
-        let nfa2 = Self::new(n2, env);
+        let nfa = Self::new(n, env);
 
-        // start -- epsilon --> _
+        // start -- epsilon --> nfa.start
         let mut start_trans = HashMap::new();
         let mut start_trans_accepts = HashSet::new();
-        // start -- epsilon --> nfa1.start
-        start_trans_accepts.insert(nfa1.start);
-        // start -- epsilon --> nfa2.start
-        start_trans_accepts.insert(nfa2.start);
+        start_trans_accepts.insert(nfa.start);
         start_trans.insert(NfaTrans::Epsilon, start_trans_accepts);
 
         states.insert(start, start_trans);
 
-        // nfa1.accept -- epsilon --> accept
-        let mut a1_trans = HashMap::new();
-        let mut a1_trans_accepts = HashSet::new();
-        a1_trans_accepts.insert(accept);
-        a1_trans.insert(NfaTrans::Epsilon, a1_trans_accepts);
-
-        states.insert(nfa1.accept, a1_trans);
-
-        // nfa2.accept -- epsilon --> accept
-        let mut a2_trans = HashMap::new();
-        let mut a2_trans_accepts = HashSet::new();
-        a2_trans_accepts.insert(accept);
-        a2_trans.insert(NfaTrans::Epsilon, a2_trans_accepts);
+        // nfa.accept -- epsilon --> _
+        let mut a_trans = HashMap::new();
+        let mut a_trans_accepts = HashSet::new();
+        // nfa.accept -- epsilon --> start
+        a_trans_accepts.insert(start);
+        // nfa.accept -- epsilon --> accept
+        a_trans_accepts.insert(accept);
+        a_trans.insert(NfaTrans::Epsilon, a_trans_accepts);
 
-        states.insert(nfa2.accept, a2_trans);
+        states.insert(nfa.accept, a_trans);
 
-        states.extend(nfa1.states);
-        states.extend(nfa2.states);
+        states.extend(nfa.states);
 
         Self {
             start,
@@ -159,7 +237,7 @@ impl Nfa {
         }
     }
 
-    fn new_repeat(n: Node, env: &mut Env) -> Self {
+    fn new_optional(n: Node, env: &mut Env) -> Self {
         let start = env.next();
         let accept = env.next();
 
@@ -178,12 +256,9 @@ impl Nfa {
 
         states.insert(start, start_trans);
 
-        // nfa.accept -- epsilon --> _
+        // nfa.accept -- epsilon --> accept
         let mut a_trans = HashMap::new();
         let mut a_trans_accepts = HashSet::new();
-        // nfa.accept -- epsilon --> start
-        a_trans_accepts.insert(start);
-        // nfa.accept -- epsilon --> accept
         a_trans_accepts.insert(accept);
         a_trans.insert(NfaTrans::Epsilon, a_trans_accepts);
 
@@ -198,15 +273,293 @@ impl Nfa {
         }
     }
 
+    // 各文字を f で記号に置き換えた NFA。同じ記号になる文字の遷移はまとめる
+    pub(crate) fn map_symbols<S: Symbol>(&self, f: impl Fn(char) -> S) -> Nfa<S> {
+        let states = self
+            .states
+            .iter()
+            .map(|(s, transs)| {
+                let mut mapped: HashMap<NfaTrans<S>, HashSet<NfaState>> = HashMap::new();
+
+                for (t, nexts) in transs {
+                    let t = match t {
+                        NfaTrans::Epsilon => NfaTrans::Epsilon,
+                        NfaTrans::Char(c) => NfaTrans::Char(f(*c)),
+                        NfaTrans::Any => NfaTrans::Any,
+                        NfaTrans::Look(l) => NfaTrans::Look(*l),
+                    };
+
+                    mapped.entry(t).or_default().extend(nexts);
+                }
+
+                (*s, mapped)
+            })
+            .collect();
+
+        Nfa {
+            start: self.start,
+            states,
+            accept: self.accept,
+        }
+    }
+}
+
+impl<S: Symbol> Nfa<S> {
     pub fn start(&self) -> NfaState {
         self.start
     }
 
-    pub fn states(&self) -> &HashMap<NfaState, HashMap<NfaTrans, HashSet<NfaState>>> {
+    pub fn states(&self) -> &HashMap<NfaState, HashMap<NfaTrans<S>, HashSet<NfaState>>> {
         &self.states
     }
 
     pub fn accept(&self) -> NfaState {
         self.accept
     }
+
+    // 遷移元・遷移先として現れるすべての状態の数
+    pub fn num_states(&self) -> usize {
+        let mut states = HashSet::new();
+        states.insert(self.start);
+        states.insert(self.accept);
+
+        for (s, transs) in self.states.iter() {
+            states.insert(*s);
+            states.extend(transs.values().flatten());
+        }
+
+        states.len()
+    }
+
+    // 開始状態から遷移があり、受理状態のみが遷移を持たない終端の状態であり、
+    // すべての遷移先が定義されていて、すべての状態に開始状態から到達できることを確かめる
+    pub(crate) fn validate(&self) -> Result<(), NfaError> {
+        if self.start != self.accept && !self.states.contains_key(&self.start) {
+            return Err(NfaError::MissingStart(self.start));
+        }
+
+        if self.states.contains_key(&self.accept) {
+            return Err(NfaError::AcceptHasTransitions(self.accept));
+        }
+
+        let mut sorted: Vec<(&NfaState, &HashMap<_, _>)> = self.states.iter().collect();
+        sorted.sort_by_key(|(s, _)| **s);
+
+        for (from, transs) in sorted.iter() {
+            let mut targets: Vec<&NfaState> = transs.values().flatten().collect();
+            targets.sort();
+
+            if let Some(to) = targets
+                .into_iter()
+                .find(|to| **to != self.accept && !self.states.contains_key(to))
+            {
+                return Err(NfaError::DanglingTarget {
+                    from: **from,
+                    to: *to,
+                });
+            }
+        }
+
+        let mut visited = HashSet::new();
+        visited.insert(self.start);
+        let mut stack = vec![self.start];
+
+        while let Some(s) = stack.pop() {
+            for next in self
+                .states
+                .get(&s)
+                .into_iter()
+                .flat_map(|t| t.values().flatten())
+            {
+                if visited.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+
+        if let Some((s, _)) = sorted.iter().find(|(s, _)| !visited.contains(*s)) {
+            return Err(NfaError::Unreachable(**s));
+        }
+
+        if !visited.contains(&self.accept) {
+            return Err(NfaError::Unreachable(self.accept));
+        }
+
+        Ok(())
+    }
+}
+
+// 開始状態から到達できる状態を幅優先で、遷移の順序を固定して列挙する
+// 各状態は遷移の種類ごとに、遷移先を番号の順に並べたものと共に一度だけ返される
+pub struct NfaBfs<'a> {
+    nfa: &'a Nfa,
+    visited: HashSet<NfaState>,
+    queue: VecDeque<NfaState>,
+}
+
+impl<'a> NfaBfs<'a> {
+    pub(crate) fn new(nfa: &'a Nfa) -> Self {
+        let mut visited = HashSet::new();
+        visited.insert(nfa.start);
+
+        Self {
+            nfa,
+            visited,
+            queue: VecDeque::from([nfa.start]),
+        }
+    }
+}
+
+impl Iterator for NfaBfs<'_> {
+    type Item = (NfaState, Vec<(NfaTrans, Vec<NfaState>)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.queue.pop_front()?;
+
+        let mut transs: Vec<(NfaTrans, Vec<NfaState>)> = self
+            .nfa
+            .states
+            .get(&state)
+            .into_iter()
+            .flatten()
+            .map(|(t, nexts)| {
+                let mut nexts: Vec<NfaState> = nexts.iter().copied().collect();
+                nexts.sort();
+
+                (*t, nexts)
+            })
+            .collect();
+        transs.sort();
+
+        for next in transs.iter().flat_map(|(_, nexts)| nexts) {
+            if self.visited.insert(*next) {
+                self.queue.push_back(*next);
+            }
+        }
+
+        Some((state, transs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::{
+        lexer,
+        nfa::{Nfa, NfaError, NfaState, NfaTrans},
+        parser::Node,
+        regex::Regex,
+    };
+
+    fn trans(targets: &[u32]) -> HashMap<NfaTrans, HashSet<NfaState>> {
+        let mut transs = HashMap::new();
+        transs.insert(
+            NfaTrans::Epsilon,
+            targets.iter().map(|s| NfaState(*s)).collect(),
+        );
+
+        transs
+    }
+
+    #[test]
+    fn built_nfa_is_valid() {
+        for re in ["a", "a(b|c)*", "(a|b)+c?", r"\Aa.\z"] {
+            let nfa = Nfa::from(Node::parse(&lexer::tokenize(re).unwrap()).unwrap());
+
+            assert_eq!(nfa.validate(), Ok(()), "{re}");
+        }
+    }
+
+    #[cfg(feature = "internals")]
+    #[test]
+    fn bfs_visits_reachable_states_once() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let visited: Vec<_> = regex.iter_states_bfs().collect();
+
+        let states: HashSet<NfaState> = visited.iter().map(|(s, _)| *s).collect();
+        assert_eq!(states.len(), visited.len());
+        assert_eq!(states.len(), regex.nfa().num_states());
+
+        assert_eq!(visited[0].0, regex.nfa().start());
+        assert!(states.contains(&regex.nfa().accept()));
+
+        // 同じ NFA からは常に同じ順序で列挙される
+        let again: Vec<_> = regex.iter_states_bfs().collect();
+        assert_eq!(visited, again);
+    }
+
+    #[test]
+    fn repeat_adds_one_state() {
+        let nfa = |re: &str| Nfa::from(Node::parse(&lexer::tokenize(re).unwrap()).unwrap());
+
+        // a の2状態に受理状態を1つ加えるのみ
+        assert_eq!(nfa("a").num_states(), 2);
+        assert_eq!(nfa("a*").num_states(), 3);
+
+        // 繰り返しの起点を共有しても言語は変わらない (Pike VM の結果と比べる)
+        for re in ["a*", "(a*)*", "(a*b)*", "(a|b*)*c", "(ab+)*a", "((ab)*c?)*"] {
+            let regex = Regex::new(re).unwrap();
+            assert_eq!(regex.nfa().validate(), Ok(()));
+
+            for s in [
+                "", "a", "aa", "ab", "abab", "ba", "c", "bbc", "aba", "abcab", "abba",
+            ] {
+                assert_eq!(
+                    regex.is_full_match(s),
+                    regex.matches_with_captures_vm(s).is_some(),
+                    "{re} {s}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_nfa_fails_validation() {
+        // 1 -> 2 -> 3 (受理) のうち 2 が定義されていない
+        let nfa = Nfa {
+            start: NfaState(1),
+            states: HashMap::from([(NfaState(1), trans(&[2]))]),
+            accept: NfaState(3),
+        };
+        assert_eq!(
+            nfa.validate(),
+            Err(NfaError::DanglingTarget {
+                from: NfaState(1),
+                to: NfaState(2),
+            })
+        );
+
+        // 4 -> 3 には開始状態から到達できない
+        let nfa = Nfa {
+            start: NfaState(1),
+            states: HashMap::from([(NfaState(1), trans(&[3])), (NfaState(4), trans(&[3]))]),
+            accept: NfaState(3),
+        };
+        assert_eq!(nfa.validate(), Err(NfaError::Unreachable(NfaState(4))));
+
+        let nfa = Nfa {
+            start: NfaState(1),
+            states: HashMap::from([(NfaState(1), trans(&[1]))]),
+            accept: NfaState(3),
+        };
+        assert_eq!(nfa.validate(), Err(NfaError::Unreachable(NfaState(3))));
+
+        let nfa = Nfa {
+            start: NfaState(1),
+            states: HashMap::from([(NfaState(1), trans(&[3])), (NfaState(3), trans(&[1]))]),
+            accept: NfaState(3),
+        };
+        assert_eq!(
+            nfa.validate(),
+            Err(NfaError::AcceptHasTransitions(NfaState(3)))
+        );
+
+        let nfa: Nfa = Nfa {
+            start: NfaState(1),
+            states: HashMap::new(),
+            accept: NfaState(3),
+        };
+        assert_eq!(nfa.validate(), Err(NfaError::MissingStart(NfaState(1))));
+    }
 }
@@ -0,0 +1,232 @@
+use std::borrow::Cow;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{parser::Node, regex::Regex};
+
+/// `RegexBuilder::normalize_input` で指定する Unicode の正規化形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    Nfc, // 基底文字と結合文字を合成済みの文字にまとめる (`e\u{301}` → `é`)
+    Nfd, // 合成済みの文字を基底文字と結合文字に分解する (`é` → `e\u{301}`)
+}
+
+// 文字の列を正規化する
+fn normalize_chars(chars: impl Iterator<Item = char>, form: NormalizationForm) -> Vec<char> {
+    match form {
+        NormalizationForm::Nfc => chars.nfc().collect(),
+        NormalizationForm::Nfd => chars.nfd().collect(),
+    }
+}
+
+pub(crate) fn is_normalized(input: &str, form: NormalizationForm) -> bool {
+    match form {
+        NormalizationForm::Nfc => unicode_normalization::is_nfc(input),
+        NormalizationForm::Nfd => unicode_normalization::is_nfd(input),
+    }
+}
+
+pub(crate) fn normalize(input: &str, form: NormalizationForm) -> Cow<'_, str> {
+    if is_normalized(input, form) {
+        Cow::Borrowed(input)
+    } else {
+        Cow::Owned(normalize_chars(input.chars(), form).into_iter().collect())
+    }
+}
+
+impl Node {
+    // パターン中のリテラルを正規化する
+    // NFD では合成済みの文字を分解した文字の連接にし (`é*` は `(?:e\u{301})*` になる)、
+    // 連接の中で隣り合うリテラルの並びはまとめて正規化する (NFC での合成や結合文字の並べ替えはこの中でのみ行い、
+    // `e\u{301}*` の `\u{301}*` は合成しない)
+    pub(crate) fn normalized(self, form: NormalizationForm) -> Node {
+        match self {
+            Self::Char(c) => {
+                let mut chars = normalize_chars(std::iter::once(c), form);
+
+                if chars.len() == 1 {
+                    Self::Char(chars.remove(0))
+                } else {
+                    Self::Concat(chars.into_iter().map(Self::Char).collect())
+                }
+            }
+            Self::Empty | Self::Any | Self::Look(_) => self,
+            Self::Concat(items) => {
+                let mut flat = Vec::new();
+
+                for n in items {
+                    match n.normalized(form) {
+                        Self::Concat(ns) => flat.extend(ns),
+                        n => flat.push(n),
+                    }
+                }
+
+                let mut normalized: Vec<Node> = Vec::with_capacity(flat.len());
+                let mut literals = Vec::new();
+
+                for n in flat.into_iter().chain(std::iter::once(Self::Empty)) {
+                    if let Self::Char(c) = n {
+                        literals.push(c);
+                        continue;
+                    }
+
+                    normalized.extend(
+                        normalize_chars(literals.drain(..), form)
+                            .into_iter()
+                            .map(Self::Char),
+                    );
+
+                    // 末尾に加えた Empty は並びの区切りのためのもので、結果には含めない
+                    if n != Self::Empty {
+                        normalized.push(n);
+                    }
+                }
+
+                let mut flat = normalized;
+
+                match flat.len() {
+                    0 => Self::Empty,
+                    1 => flat.remove(0),
+                    _ => Self::Concat(flat),
+                }
+            }
+            Self::Alt(branches) => {
+                Self::Alt(branches.into_iter().map(|n| n.normalized(form)).collect())
+            }
+            Self::Repeat(n) => Self::Repeat(Box::new(n.normalized(form))),
+            Self::Plus(n) => Self::Plus(Box::new(n.normalized(form))),
+            Self::Optional(n) => Self::Optional(Box::new(n.normalized(form))),
+            Self::Group(n, name) => Self::Group(Box::new(n.normalized(form)), name),
+        }
+    }
+}
+
+impl Regex {
+    /// ビルダーの `normalize_input` で指定した形式に入力を正規化する (指定が無い場合はそのまま返す)
+    /// `find` などの位置を返すメソッドには、これで正規化した入力を渡す
+    pub fn normalize<'h>(&self, input: &'h str) -> Cow<'h, str> {
+        match self.options().normalization() {
+            Some(form) => normalize(input, form),
+            None => Cow::Borrowed(input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        normalize::{self, NormalizationForm},
+        regex::Regex,
+    };
+
+    #[test]
+    fn normalize_works() {
+        assert_eq!(
+            normalize::normalize("e\u{301}", NormalizationForm::Nfc),
+            "é"
+        );
+        assert_eq!(
+            normalize::normalize("é", NormalizationForm::Nfd),
+            "e\u{301}"
+        );
+        // 2段階の分解・合成
+        assert_eq!(
+            normalize::normalize("ǖ", NormalizationForm::Nfd),
+            "u\u{308}\u{304}"
+        );
+        assert_eq!(
+            normalize::normalize("u\u{308}\u{304}", NormalizationForm::Nfc),
+            "ǖ"
+        );
+        assert_eq!(
+            normalize::normalize("か\u{3099}", NormalizationForm::Nfc),
+            "が"
+        );
+        assert!(matches!(
+            normalize::normalize("abc", NormalizationForm::Nfc),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn normalize_other_scripts() {
+        // ギリシャ文字と、結合文字の並べ替え (正準順序) が必要な文字
+        assert_eq!(
+            normalize::normalize("\u{3B1}\u{301}", NormalizationForm::Nfc),
+            "ά"
+        );
+        assert_eq!(
+            normalize::normalize("a\u{302}\u{323}", NormalizationForm::Nfc),
+            "ậ"
+        );
+        assert_eq!(
+            normalize::normalize("ậ", NormalizationForm::Nfd),
+            "a\u{323}\u{302}"
+        );
+
+        let nfc = Regex::builder().normalize_input(NormalizationForm::Nfc);
+        assert!(nfc.build_from("ά").unwrap().is_full_match("\u{3B1}\u{301}"));
+        assert!(
+            nfc.build_from("a\u{302}\u{323}")
+                .unwrap()
+                .is_full_match("ậ")
+        );
+
+        let nfd = Regex::builder().normalize_input(NormalizationForm::Nfd);
+        assert!(
+            nfd.build_from("ậ")
+                .unwrap()
+                .is_full_match("a\u{302}\u{323}")
+        );
+    }
+
+    #[test]
+    fn normalized_input_in_every_method() {
+        use crate::matcher::PartialMatch;
+
+        let regex = Regex::builder()
+            .normalize_input(NormalizationForm::Nfc)
+            .build_from("xé")
+            .unwrap();
+        let input = "xe\u{301}";
+
+        assert!(regex.is_match(input));
+        assert_eq!(regex.validate(input), Ok(()));
+        assert_eq!(regex.partial_match("x"), PartialMatch::Partial);
+        assert_eq!(regex.partial_match(input), PartialMatch::Full);
+        assert!(regex.lazy_dfa(8).matches(input));
+        assert_eq!(
+            regex.matches_try_chars(input.chars().map(Ok::<_, ()>)),
+            Ok(true)
+        );
+
+        // 位置は正規化済みの入力でのみ求められる
+        let normalized = regex.normalize(input);
+        assert_eq!(regex.find(&normalized).map(|m| m.range()), Some(0..3));
+        assert!(std::panic::catch_unwind(|| regex.find(input)).is_err());
+        assert!(std::panic::catch_unwind(|| regex.captures(input)).is_err());
+        assert!(std::panic::catch_unwind(|| regex.matcher()).is_err());
+    }
+
+    #[test]
+    fn matches_normalized_input() {
+        let nfc = Regex::builder().normalize_input(NormalizationForm::Nfc);
+        let nfd = Regex::builder().normalize_input(NormalizationForm::Nfd);
+
+        let regex = nfc.build_from("é").unwrap();
+        assert!(regex.is_full_match("e\u{301}"));
+        assert!(regex.is_full_match("é"));
+        assert!(regex.is_match("cafe\u{301}"));
+
+        let regex = nfc.build_from("(e\u{301})+").unwrap();
+        assert!(regex.is_full_match("e\u{301}é"));
+
+        // NFD では合成済みの文字はまとめて繰り返す
+        let regex = nfd.build_from("(é|が)*").unwrap();
+        assert!(regex.is_full_match("e\u{301}éが"));
+        assert!(!regex.is_full_match("e\u{301}\u{301}"));
+        assert_eq!(regex.normalize("がe"), "か\u{3099}e");
+
+        assert!(!Regex::new("é").unwrap().is_full_match("e\u{301}"));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::parser::Node;
+
+impl Node {
+    // `abc|abd` を `ab(c|d)` のように、すべての分岐に共通する文字の接頭辞・接尾辞を括り出す
+    // 括り出した部分はグループにしないため、キャプチャの番号は変わらない
+    pub(crate) fn factor_alternation(self) -> Self {
+        match self {
+            Self::Empty | Self::Char(_) | Self::Any | Self::Look(_) => self,
+            Self::Concat(items) => {
+                Self::Concat(items.into_iter().map(Self::factor_alternation).collect())
+            }
+            Self::Repeat(n) => Self::Repeat(Box::new(n.factor_alternation())),
+            Self::Plus(n) => Self::Plus(Box::new(n.factor_alternation())),
+            Self::Optional(n) => Self::Optional(Box::new(n.factor_alternation())),
+            Self::Group(n, name) => Self::Group(Box::new(n.factor_alternation()), name),
+            Self::Alt(_) => {
+                let mut branches = Vec::new();
+                self.flatten_alt(&mut branches);
+
+                let branches: Vec<Vec<Node>> = branches
+                    .into_iter()
+                    .map(|b| {
+                        let mut items = Vec::new();
+                        b.factor_alternation().flatten_concat(&mut items);
+
+                        items
+                    })
+                    .collect();
+
+                Self::factor_branches(branches)
+            }
+        }
+    }
+
+    // 一致する文字列が必ず始まる文字の列と、それがノード全体 (ノードが文字列そのもの) であるかを返す
+    pub(crate) fn literal_prefix(&self) -> (String, bool) {
+        match self {
+            Self::Empty | Self::Look(_) => (String::new(), true),
+            Self::Char(c) => (c.to_string(), true),
+            Self::Any | Self::Repeat(_) | Self::Optional(_) => (String::new(), false),
+            Self::Plus(n) => (n.literal_prefix().0, false),
+            Self::Group(n, _) => n.literal_prefix(),
+            Self::Concat(items) => {
+                let mut prefix = String::new();
+
+                for n in items {
+                    let (p, complete) = n.literal_prefix();
+                    prefix.push_str(&p);
+
+                    if !complete {
+                        return (prefix, false);
+                    }
+                }
+
+                (prefix, true)
+            }
+            Self::Alt(branches) => {
+                let prefixes: Vec<(String, bool)> =
+                    branches.iter().map(Self::literal_prefix).collect();
+                let (first, _) = &prefixes[0];
+
+                let prefix = prefixes
+                    .iter()
+                    .fold(first.clone(), |acc, (p, _)| common_prefix(&acc, p));
+                let complete = prefixes.iter().all(|(p, c)| *c && *p == prefix);
+
+                (prefix, complete)
+            }
+        }
+    }
+
+    // 一致する文字列の長さ (文字数) の最小値と最大値。最大値が無い場合は None
+    pub(crate) fn len_bounds(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Empty | Self::Look(_) => (0, Some(0)),
+            Self::Char(_) | Self::Any => (1, Some(1)),
+            Self::Group(n, _) => n.len_bounds(),
+            Self::Optional(n) => (0, n.len_bounds().1),
+            Self::Repeat(n) | Self::Plus(n) => {
+                let (min, max) = n.len_bounds();
+                let min = if matches!(self, Self::Plus(_)) {
+                    min
+                } else {
+                    0
+                };
+
+                // 空文字列にしか一致しないものは何回繰り返しても空文字列
+                (min, (max == Some(0)).then_some(0))
+            }
+            Self::Concat(items) => {
+                items
+                    .iter()
+                    .map(Self::len_bounds)
+                    .fold((0, Some(0)), |(min, max), (m, n)| {
+                        (
+                            min.saturating_add(m),
+                            max.zip(n).and_then(|(max, n)| max.checked_add(n)),
+                        )
+                    })
+            }
+            Self::Alt(branches) => {
+                let bounds: Vec<(usize, Option<usize>)> =
+                    branches.iter().map(Self::len_bounds).collect();
+
+                let min = bounds.iter().map(|(m, _)| *m).min().unwrap_or(0);
+                let max = bounds
+                    .iter()
+                    .map(|(_, n)| *n)
+                    .try_fold(0, |acc, n| n.map(|n| acc.max(n)));
+
+                (min, max)
+            }
+        }
+    }
+
+    fn factor_branches(branches: Vec<Vec<Node>>) -> Self {
+        // 空の分岐は表現できないため、各分岐に少なくとも1要素は残す
+        let max = branches.iter().map(|b| b.len() - 1).min().unwrap_or(0);
+
+        let prefix_len = (0..max)
+            .take_while(|i| Self::all_same_char(branches.iter().map(|b| &b[*i])))
+            .count();
+        let suffix_len = (0..max - prefix_len)
+            .take_while(|i| Self::all_same_char(branches.iter().map(|b| &b[b.len() - 1 - i])))
+            .count();
+
+        let prefix: Vec<Node> = branches[0][..prefix_len].to_vec();
+        let suffix: Vec<Node> = branches[0][branches[0].len() - suffix_len..].to_vec();
+
+        let alt = Self::Alt(
+            branches
+                .iter()
+                .map(|b| {
+                    let rest = b[prefix_len..b.len() - suffix_len].to_vec();
+
+                    Self::concat_all(rest)
+                })
+                .collect(),
+        );
+
+        let mut items = prefix;
+        items.push(alt);
+        items.extend(suffix);
+
+        Self::concat_all(items)
+    }
+
+    fn all_same_char<'a>(mut nodes: impl Iterator<Item = &'a Node>) -> bool {
+        match nodes.next() {
+            Some(first @ Self::Char(_)) => nodes.all(|n| n == first),
+            _ => false,
+        }
+    }
+
+    fn flatten_alt(self, branches: &mut Vec<Node>) {
+        if let Self::Alt(bs) = self {
+            bs.into_iter().for_each(|b| b.flatten_alt(branches));
+        } else {
+            branches.push(self);
+        }
+    }
+
+    fn flatten_concat(self, items: &mut Vec<Node>) {
+        if let Self::Concat(ns) = self {
+            ns.into_iter().for_each(|n| n.flatten_concat(items));
+        } else {
+            items.push(self);
+        }
+    }
+
+    // 空でない要素列を連接にする
+    fn concat_all(mut items: Vec<Node>) -> Self {
+        match items.len() {
+            0 => unreachable!("concatenation has at least one item"),
+            1 => items.remove(0),
+            _ => Self::Concat(items),
+        }
+    }
+}
+
+// 2つの文字列に共通する最長の接頭辞
+pub(crate) fn common_prefix(s1: &str, s2: &str) -> String {
+    s1.chars()
+        .zip(s2.chars())
+        .take_while(|(c1, c2)| c1 == c2)
+        .map(|(c, _)| c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer, nfa::Nfa, parser::Node, regex::Regex};
+
+    #[test]
+    fn factor_common_prefix() {
+        let ast = Node::parse(&lexer::tokenize("abc|abd").unwrap()).unwrap();
+        let factored = ast.clone().factor_alternation();
+
+        assert_eq!(
+            factored,
+            Node::parse(&lexer::tokenize("ab(c|d)").unwrap())
+                .unwrap()
+                .ungrouped()
+        );
+        assert!(Nfa::from(factored).num_states() < Nfa::from(ast).num_states());
+
+        let regex = Regex::new("abc|abd").unwrap();
+        let optimized = Regex::builder()
+            .optimize(true)
+            .build_from("abc|abd")
+            .unwrap();
+
+        for s in ["abc", "abd", "ab", "abe", "abcd", ""] {
+            assert_eq!(regex.is_full_match(s), optimized.is_full_match(s));
+        }
+    }
+
+    #[test]
+    fn factor_common_suffix() {
+        let ast = Node::parse(&lexer::tokenize("xab|yab|zb").unwrap()).unwrap();
+
+        assert_eq!(
+            ast.factor_alternation(),
+            Node::parse(&lexer::tokenize("(xa|ya|z)b").unwrap())
+                .unwrap()
+                .ungrouped()
+        );
+    }
+
+    #[test]
+    fn factor_keeps_branches_non_empty() {
+        // 共通部分をすべて括り出すと ab の分岐が空になってしまう
+        let ast = Node::parse(&lexer::tokenize("ab|abc").unwrap()).unwrap();
+
+        assert_eq!(
+            ast.factor_alternation(),
+            Node::parse(&lexer::tokenize("a(b|bc)").unwrap())
+                .unwrap()
+                .ungrouped()
+        );
+    }
+
+    #[test]
+    fn len_bounds_works() {
+        let bounds = |re: &str| {
+            Node::parse(&lexer::tokenize(re).unwrap())
+                .unwrap()
+                .len_bounds()
+        };
+
+        assert_eq!(bounds("abc"), (3, Some(3)));
+        assert_eq!(bounds("a(b|cd)?"), (1, Some(3)));
+        assert_eq!(bounds("ab+"), (2, None));
+        assert_eq!(bounds("a(\\z)*"), (1, Some(1)));
+        assert_eq!(bounds("a{2,3}.|x"), (1, Some(4)));
+    }
+
+    #[test]
+    fn literal_prefix_works() {
+        let prefix = |re: &str| {
+            Node::parse(&lexer::tokenize(re).unwrap())
+                .unwrap()
+                .literal_prefix()
+        };
+
+        assert_eq!(prefix("abc"), ("abc".to_string(), true));
+        assert_eq!(prefix("ab(c|d)"), ("ab".to_string(), false));
+        assert_eq!(prefix("a(bc)+d"), ("abc".to_string(), false));
+        assert_eq!(prefix("(ab|ab)c"), ("abc".to_string(), true));
+        assert_eq!(prefix("a?b"), ("".to_string(), false));
+    }
+}
@@ -1,11 +1,23 @@
 use crate::lexer::TokenKind;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Look {
+    StartText, // \A
+    EndText,   // \z
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Node {
+    Empty,
     Char(char),
-    Concat(Box<Node>, Box<Node>),
-    Or(Box<Node>, Box<Node>),
+    Look(Look),
+    Concat(Vec<Node>), // 2つ以上の要素
+    Alt(Vec<Node>),    // 2つ以上の分岐
+    Any,
     Repeat(Box<Node>),
+    Plus(Box<Node>),
+    Optional(Box<Node>),
+    Group(Box<Node>, Option<String>), // グループの名前 (`(?<name>...)`)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -13,90 +25,456 @@ pub(crate) enum ParseError {
     UnexpectedEOF,
     UnexpectedToken(TokenKind, Vec<TokenKind>),
     ExpectedEOF(TokenKind),
+    TooDeeplyNested(usize),      // 括弧の入れ子の上限
+    InvalidRepetition(u32, u32), // `{n,m}` で n > m
+    TooLarge(usize),             // 展開後のノード数の上限
+    EmptyGroupName,              // `(?<>...)`
+    DuplicateGroupName(String),  // 同じ名前のグループが2つ以上ある
+}
+
+// `(?i:...)` などで括弧の中でのみ有効になる指定
+#[derive(Debug, Clone, Copy, Default)]
+struct Flags {
+    case_insensitive: bool, // i: 大文字と小文字を区別しない
 }
 
+// パース・NFA の構築などは括弧の入れ子について再帰するため、スタックを使い切らないよう深さを制限する
+pub(crate) const MAX_NESTING: usize = 256;
+
+// `{n,m}` を展開した結果のノード数の上限 (ビルダーの `size_limit` に関わらずこれを超えるとエラーになる)
+pub(crate) const MAX_SIZE: usize = 1 << 22;
+
 impl Node {
+    // 大文字と小文字を区別するパース (テストでパターンから直接 AST を作るときに使う)
+    #[cfg(test)]
     pub(crate) fn parse(tokens: &[TokenKind]) -> Result<Self, ParseError> {
+        Self::parse_with(tokens, false)
+    }
+
+    // case_insensitive が真の場合、パターン全体を `(?i:...)` で囲んだものとしてパースする
+    pub(crate) fn parse_with(
+        tokens: &[TokenKind],
+        case_insensitive: bool,
+    ) -> Result<Self, ParseError> {
+        let mut depth: usize = 0;
+
+        for t in tokens {
+            match t {
+                TokenKind::LPare => depth += 1,
+                TokenKind::RPare => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+
+            if depth > MAX_NESTING {
+                return Err(ParseError::TooDeeplyNested(MAX_NESTING));
+            }
+        }
+
         let mut tokens = tokens.iter().peekable();
 
-        let seq = Self::parse_sequence(&mut tokens)?;
+        let alt = Self::parse_binary(&mut tokens, Flags { case_insensitive })?;
 
         if let Some(t) = tokens.next() {
-            Err(ParseError::ExpectedEOF(*t))
+            return Err(ParseError::ExpectedEOF(*t));
+        }
+
+        // 名前からグループを引けるように、同じ名前のグループは許さない
+        let mut names = Vec::new();
+        alt.group_names(&mut names);
+        let mut seen = std::collections::HashSet::new();
+        if let Some(name) = names
+            .into_iter()
+            .flatten()
+            .find(|n| !seen.insert(n.clone()))
+        {
+            return Err(ParseError::DuplicateGroupName(name));
+        }
+
+        Ok(alt)
+    }
+
+    // `|` は連接よりも結合が弱いため、`|` で区切られた連接をすべて読んで1つの Alt にする
+    // `a|` や `|a` のような空の分岐は空文字列 (Empty) になる。`|` の無い空のパターンはエラーのまま
+    fn parse_binary(
+        tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+        flags: Flags,
+    ) -> Result<Self, ParseError> {
+        let first = match tokens.peek() {
+            Some(TokenKind::Bar) => Self::Empty,
+            _ => Self::parse_sequence(tokens, flags)?,
+        };
+        let mut branches = vec![first];
+
+        while let Some(TokenKind::Bar) = tokens.peek() {
+            tokens.next();
+
+            let branch = match tokens.peek() {
+                None | Some(TokenKind::Bar | TokenKind::RPare) => Self::Empty,
+                _ => Self::parse_sequence(tokens, flags)?,
+            };
+            branches.push(branch);
+        }
+
+        if branches.len() == 1 {
+            Ok(branches.remove(0))
         } else {
-            Ok(seq)
+            Ok(Self::Alt(branches))
         }
     }
 
-    pub(crate) fn parse_sequence(
+    fn parse_sequence(
         tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+        flags: Flags,
     ) -> Result<Self, ParseError> {
-        let mut left = Self::parse_binary(tokens)?;
+        let mut items = vec![Self::parse_unary(tokens, flags)?];
 
         while let Some(t) = tokens.peek() {
-            if matches!(t, TokenKind::Char(_) | TokenKind::LPare) {
-                let right = Self::parse_binary(tokens)?;
+            // 明示的な連接の記号の後には必ず要素が続く
+            if let TokenKind::Concat(_) = t {
+                tokens.next();
 
-                left = Self::Concat(Box::new(left), Box::new(right));
+                items.push(Self::parse_unary(tokens, flags)?);
+            } else if matches!(
+                t,
+                TokenKind::Char(_)
+                    | TokenKind::LPare
+                    | TokenKind::Dot
+                    | TokenKind::Caret
+                    | TokenKind::Dollar
+                    | TokenKind::StartText
+                    | TokenKind::EndText
+            ) {
+                items.push(Self::parse_unary(tokens, flags)?);
             } else {
-                return Ok(left);
+                break;
             }
         }
 
-        Ok(left)
+        if items.len() == 1 {
+            Ok(items.remove(0))
+        } else {
+            Ok(Self::Concat(items))
+        }
     }
 
-    fn parse_binary(
+    fn parse_unary(
         tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+        flags: Flags,
     ) -> Result<Self, ParseError> {
-        let left = Self::parse_unary(tokens)?;
+        let left = Self::parse_atomic(tokens, flags)?;
 
-        if let Some(TokenKind::Bar) = tokens.peek() {
-            tokens.next();
+        match tokens.peek() {
+            Some(TokenKind::Star) => {
+                tokens.next();
+
+                Ok(Self::Repeat(Box::new(left)))
+            }
+            Some(TokenKind::Plus) => {
+                tokens.next();
+
+                Ok(Self::Plus(Box::new(left)))
+            }
+            Some(TokenKind::Question) => {
+                tokens.next();
+
+                Ok(Self::Optional(Box::new(left)))
+            }
+            Some(TokenKind::LBrace) => {
+                tokens.next();
+
+                let (min, max) = Self::parse_bounds(tokens)?;
 
-            let right = Self::parse_unary(tokens)?;
+                left.unroll(min, max)
+            }
+            _ => Ok(left),
+        }
+    }
 
-            Ok(Self::Or(Box::new(left), Box::new(right)))
+    // `{` の後の `n}` / `n,}` / `n,m}` を読む。`n,` の場合の上限は None
+    fn parse_bounds(
+        tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+    ) -> Result<(u32, Option<u32>), ParseError> {
+        let min = Self::parse_number(tokens)?;
+
+        let max = if tokens.next_if_eq(&&TokenKind::Char(',')).is_some() {
+            if let Some(TokenKind::RBrace) = tokens.peek() {
+                None
+            } else {
+                Some(Self::parse_number(tokens)?)
+            }
         } else {
-            Ok(left)
+            Some(min)
+        };
+
+        Self::consume_token(tokens, TokenKind::RBrace)?;
+
+        match max {
+            Some(max) if max < min => Err(ParseError::InvalidRepetition(min, max)),
+            _ => Ok((min, max)),
         }
     }
 
-    fn parse_unary(
+    fn parse_number(
         tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
-    ) -> Result<Self, ParseError> {
-        let left = Self::parse_atomic(tokens)?;
+    ) -> Result<u32, ParseError> {
+        let mut n: Option<u32> = None;
 
-        if let Some(TokenKind::Star) = tokens.peek() {
+        while let Some(TokenKind::Char(c @ '0'..='9')) = tokens.peek() {
             tokens.next();
 
-            Ok(Self::Repeat(Box::new(left)))
+            let digit = c.to_digit(10).unwrap_or(0);
+            n = Some(
+                n.unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(digit))
+                    .ok_or(ParseError::TooLarge(MAX_SIZE))?,
+            );
+        }
+
+        match (n, tokens.peek()) {
+            (Some(n), _) => Ok(n),
+            (None, Some(t)) => Err(ParseError::UnexpectedToken(**t, vec![TokenKind::Char('0')])),
+            (None, None) => Err(ParseError::UnexpectedEOF),
+        }
+    }
+
+    // `{min,max}` を連接・`?`・`*`・`+` に展開する
+    fn unroll(self, min: u32, max: Option<u32>) -> Result<Self, ParseError> {
+        let copies = max.unwrap_or(min).max(1) as usize;
+
+        if self.size().saturating_mul(copies) > MAX_SIZE {
+            return Err(ParseError::TooLarge(MAX_SIZE));
+        }
+
+        let concat = |mut items: Vec<Self>| match items.len() {
+            0 => Self::Empty,
+            1 => items.remove(0),
+            _ => Self::Concat(items),
+        };
+        let ungrouped = self.ungrouped();
+        let mut items = Vec::new();
+
+        // グループを含まない場合は min 回の後に max - min 回の `?` (上限が無い場合は `*`) を続ける
+        if ungrouped == self {
+            for _ in 0..min {
+                items.push(self.clone());
+            }
+
+            match max {
+                Some(max) => {
+                    for _ in min..max {
+                        items.push(Self::Optional(Box::new(self.clone())));
+                    }
+                }
+                None => items.push(Self::Repeat(Box::new(self))),
+            }
+
+            return Ok(concat(items));
+        }
+
+        // グループを含む場合は、グループを最後の1回分にのみ残すため、グループの番号は展開しない場合と同じになる
+        // `(a){1,3}` を `a?a?(a)` とするように最後の1回を末尾に置き、`(a)*` と同じく最後の繰り返しの範囲を得る
+        let last = match max {
+            Some(0) => return Ok(Self::Empty),
+            Some(max) => {
+                for _ in 1..min {
+                    items.push(ungrouped.clone());
+                }
+                for _ in min.max(1)..max {
+                    items.push(Self::Optional(Box::new(ungrouped.clone())));
+                }
+
+                self
+            }
+            None if min == 0 => return Ok(Self::Repeat(Box::new(self))),
+            None => {
+                for _ in 1..min {
+                    items.push(ungrouped.clone());
+                }
+
+                Self::Plus(Box::new(self))
+            }
+        };
+        items.push(last);
+
+        if min == 0 {
+            Ok(Self::Optional(Box::new(concat(items))))
         } else {
-            Ok(left)
+            Ok(concat(items))
+        }
+    }
+
+    // ノードの総数
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Self::Empty | Self::Char(_) | Self::Any | Self::Look(_) => 1,
+            Self::Concat(ns) | Self::Alt(ns) => 1 + ns.iter().map(Self::size).sum::<usize>(),
+            Self::Repeat(n) | Self::Plus(n) | Self::Optional(n) | Self::Group(n, _) => 1 + n.size(),
+        }
+    }
+
+    // グループを取り除いた (括弧としてのみ働く) ノード
+    pub(crate) fn ungrouped(&self) -> Self {
+        match self {
+            Self::Empty | Self::Char(_) | Self::Any | Self::Look(_) => self.clone(),
+            Self::Concat(ns) => Self::Concat(ns.iter().map(Self::ungrouped).collect()),
+            Self::Alt(ns) => Self::Alt(ns.iter().map(Self::ungrouped).collect()),
+            Self::Repeat(n) => Self::Repeat(Box::new(n.ungrouped())),
+            Self::Plus(n) => Self::Plus(Box::new(n.ungrouped())),
+            Self::Optional(n) => Self::Optional(Box::new(n.ungrouped())),
+            Self::Group(n, _) => n.ungrouped(),
+        }
+    }
+
+    // グループの名前を、グループが現れる順 (`(` の位置の順) に names に加える
+    pub(crate) fn group_names(&self, names: &mut Vec<Option<String>>) {
+        match self {
+            Self::Empty | Self::Char(_) | Self::Any | Self::Look(_) => {}
+            Self::Concat(ns) | Self::Alt(ns) => ns.iter().for_each(|n| n.group_names(names)),
+            Self::Repeat(n) | Self::Plus(n) | Self::Optional(n) => n.group_names(names),
+            Self::Group(n, name) => {
+                names.push(name.clone());
+                n.group_names(names);
+            }
         }
     }
 
     fn parse_atomic(
         tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+        flags: Flags,
     ) -> Result<Self, ParseError> {
         let t = tokens.next().ok_or(ParseError::UnexpectedEOF)?;
 
         match t {
+            TokenKind::Char(c) if flags.case_insensitive => Ok(Self::case_folded(*c)),
             TokenKind::Char(c) => Ok(Self::Char(*c)),
+            TokenKind::Dot => Ok(Self::Any),
+            // 複数行モードは無いため `^` / `$` は文字列の先頭・終端を表す
+            TokenKind::Caret | TokenKind::StartText => Ok(Self::Look(Look::StartText)),
+            TokenKind::Dollar | TokenKind::EndText => Ok(Self::Look(Look::EndText)),
             TokenKind::LPare => {
-                let seq = Self::parse_sequence(tokens)?;
+                // `(?:...)` はグループを作らずに括弧の中身をそのまま返す
+                // `(?i:...)` などの指定は括弧の中でのみ有効で、括弧の後は元の指定に戻る
+                // `(?<name>...)` は名前付きのグループになる
+                let mut capturing = tokens.next_if_eq(&&TokenKind::Question).is_none();
+                let mut name = None;
+
+                let flags = if capturing {
+                    flags
+                } else if tokens.next_if_eq(&&TokenKind::Char('<')).is_some() {
+                    capturing = true;
+                    name = Some(Self::parse_group_name(tokens)?);
+
+                    flags
+                } else {
+                    Self::parse_flags(tokens, flags)?
+                };
+
+                // 空の括弧 `()` / `(?:)` の中身は空文字列とする
+                let alt = if tokens.peek() == Some(&&TokenKind::RPare) {
+                    Self::Empty
+                } else {
+                    Self::parse_binary(tokens, flags)?
+                };
 
                 Self::consume_token(tokens, TokenKind::RPare)?;
 
-                Ok(seq)
+                if capturing {
+                    Ok(Self::Group(Box::new(alt), name))
+                } else {
+                    Ok(alt)
+                }
             }
-            _ => Err(ParseError::UnexpectedToken(
+            // 文字クラスは未対応で、回数指定は繰り返す対象の後にのみ書ける
+            TokenKind::RPare
+            | TokenKind::Bar
+            | TokenKind::Star
+            | TokenKind::Plus
+            | TokenKind::Question
+            | TokenKind::LBracket
+            | TokenKind::RBracket
+            | TokenKind::LBrace
+            | TokenKind::RBrace
+            | TokenKind::Concat(_) => Err(ParseError::UnexpectedToken(
                 *t,
                 vec![TokenKind::Char('c'), TokenKind::LPare],
             )),
         }
     }
 
+    // `(?<` の後のグループの名前と `>` を読む。名前は1文字以上の英数字と `_` からなる
+    fn parse_group_name(
+        tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+    ) -> Result<String, ParseError> {
+        let mut name = String::new();
+
+        loop {
+            match tokens.next() {
+                Some(TokenKind::Char('>')) if name.is_empty() => {
+                    return Err(ParseError::EmptyGroupName);
+                }
+                Some(TokenKind::Char('>')) => return Ok(name),
+                Some(TokenKind::Char(c)) if c.is_alphanumeric() || *c == '_' => name.push(*c),
+                Some(t) => {
+                    return Err(ParseError::UnexpectedToken(*t, vec![TokenKind::Char('>')]));
+                }
+                None => return Err(ParseError::UnexpectedEOF),
+            }
+        }
+    }
+
+    // `(?` の後の `i:` / `-i:` / `:` などを読み、括弧の中で有効になる指定を返す
+    // `-` より後の指定は無効にする
+    fn parse_flags(
+        tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
+        mut flags: Flags,
+    ) -> Result<Flags, ParseError> {
+        let mut enable = true;
+
+        loop {
+            match tokens.next() {
+                Some(TokenKind::Char(':')) => return Ok(flags),
+                Some(TokenKind::Char('i')) => flags.case_insensitive = enable,
+                Some(TokenKind::Char('-')) if enable => enable = false,
+                Some(t) => {
+                    return Err(ParseError::UnexpectedToken(*t, vec![TokenKind::Char(':')]));
+                }
+                None => return Err(ParseError::UnexpectedEOF),
+            }
+        }
+    }
+
+    // 大文字と小文字を区別しない文字。大文字・小文字が1文字で表せる場合のみ選択にする
+    fn case_folded(c: char) -> Self {
+        let single = |s: String| {
+            let mut cs = s.chars();
+
+            match (cs.next(), cs.next()) {
+                (Some(v), None) => Some(v),
+                _ => None,
+            }
+        };
+        let mut variants = vec![c];
+
+        for v in [
+            single(c.to_lowercase().to_string()),
+            single(c.to_uppercase().to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if !variants.contains(&v) {
+                variants.push(v);
+            }
+        }
+
+        if variants.len() == 1 {
+            Self::Char(c)
+        } else {
+            Self::Alt(variants.into_iter().map(Self::Char).collect())
+        }
+    }
+
     fn consume_token(
         tokens: &mut std::iter::Peekable<std::slice::Iter<'_, TokenKind>>,
         t: TokenKind,
@@ -115,7 +493,11 @@ impl Node {
 
 #[cfg(test)]
 mod tests {
-    use crate::{lexer::TokenKind, parser::Node};
+    use crate::{
+        lexer::{self, TokenKind},
+        parser::{Node, ParseError},
+        regex::Regex,
+    };
 
     #[test]
     fn parse_tokens() {
@@ -129,16 +511,212 @@ mod tests {
             TokenKind::Star,
         ];
 
-        let expected = Node::Concat(
-            Box::new(Node::Char('a')),
-            Box::new(Node::Repeat(Box::new(Node::Or(
-                Box::new(Node::Char('b')),
-                Box::new(Node::Char('c')),
-            )))),
-        );
+        let expected = Node::Concat(vec![
+            Node::Char('a'),
+            Node::Repeat(Box::new(Node::Group(
+                Box::new(Node::Alt(vec![Node::Char('b'), Node::Char('c')])),
+                None,
+            ))),
+        ]);
 
         let result = Node::parse(&tokens);
 
         assert_eq!(result, Ok(expected));
     }
+
+    #[test]
+    fn parse_alternation_precedence() {
+        let tokens = lexer::tokenize("ab|c|d").unwrap();
+
+        // `|` は連接より結合が弱く、すべての分岐が1つの Alt にまとまる
+        let expected = Node::Alt(vec![
+            Node::Concat(vec![Node::Char('a'), Node::Char('b')]),
+            Node::Char('c'),
+            Node::Char('d'),
+        ]);
+
+        assert_eq!(Node::parse(&tokens), Ok(expected));
+    }
+
+    #[test]
+    fn parse_concatenation_single_node() {
+        let ast = Node::parse(&lexer::tokenize("abc").unwrap()).unwrap();
+
+        assert_eq!(
+            ast,
+            Node::Concat(vec![Node::Char('a'), Node::Char('b'), Node::Char('c')])
+        );
+
+        let regex = Regex::new("abc").unwrap();
+        assert!(regex.is_full_match("abc"));
+        assert!(!regex.is_full_match("ab"));
+        assert!(!regex.is_full_match("abcc"));
+    }
+
+    #[test]
+    fn parse_alternation_single_node() {
+        let ast = Node::parse(&lexer::tokenize("a|b|c|d").unwrap()).unwrap();
+
+        assert_eq!(
+            ast,
+            Node::Alt(vec![
+                Node::Char('a'),
+                Node::Char('b'),
+                Node::Char('c'),
+                Node::Char('d'),
+            ])
+        );
+
+        let regex = Regex::new("a|b|c|d").unwrap();
+        for s in ["a", "b", "c", "d"] {
+            assert!(regex.is_full_match(s));
+        }
+        assert!(!regex.is_full_match("ab"));
+        assert!(!regex.is_full_match("e"));
+    }
+
+    #[test]
+    fn parse_empty_branch() {
+        let ast = Node::parse(&lexer::tokenize("(a|)b").unwrap()).unwrap();
+        assert_eq!(
+            ast,
+            Node::Concat(vec![
+                Node::Group(
+                    Box::new(Node::Alt(vec![Node::Char('a'), Node::Empty])),
+                    None
+                ),
+                Node::Char('b'),
+            ])
+        );
+
+        assert_eq!(
+            Node::parse(&lexer::tokenize("|a||").unwrap()),
+            Ok(Node::Alt(vec![
+                Node::Empty,
+                Node::Char('a'),
+                Node::Empty,
+                Node::Empty,
+            ]))
+        );
+
+        // 空の分岐は表示しても空の分岐になり、そのまま読み直せる
+        assert_eq!(ast.to_string(), "(a|)b");
+
+        // 空の括弧は空文字列を囲む
+        assert_eq!(
+            Node::parse(&lexer::tokenize("()").unwrap()),
+            Ok(Node::Group(Box::new(Node::Empty), None))
+        );
+        assert_eq!(
+            Node::parse(&lexer::tokenize("a(?:)*").unwrap()),
+            Ok(Node::Concat(vec![
+                Node::Char('a'),
+                Node::Repeat(Box::new(Node::Empty)),
+            ]))
+        );
+
+        // `|` の無い空のパターンはエラーのまま
+        assert!(Node::parse(&lexer::tokenize("").unwrap()).is_err());
+    }
+
+    #[test]
+    fn parse_named_group() {
+        let ast = Node::parse(&lexer::tokenize("(?<x_1>a)(b)").unwrap()).unwrap();
+        assert_eq!(
+            ast,
+            Node::Concat(vec![
+                Node::Group(Box::new(Node::Char('a')), Some("x_1".to_string())),
+                Node::Group(Box::new(Node::Char('b')), None),
+            ])
+        );
+
+        // 名前は表示しても残り、そのまま読み直せる
+        assert_eq!(ast.to_string(), "(?<x_1>a)(b)");
+
+        assert_eq!(
+            Node::parse(&lexer::tokenize("(?<>a)").unwrap()),
+            Err(ParseError::EmptyGroupName)
+        );
+        assert_eq!(
+            Node::parse(&lexer::tokenize("(?<n>a)(b)|(?<n>c)").unwrap()),
+            Err(ParseError::DuplicateGroupName("n".to_string()))
+        );
+        assert!(Node::parse(&lexer::tokenize("(?<n>a)(?<m>b)").unwrap()).is_ok());
+
+        for invalid in ["(?<a-b>a)", "(?<a", "(?<a>a"] {
+            assert!(
+                Node::parse(&lexer::tokenize(invalid).unwrap()).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_concat_marker() {
+        let build = |re: &str| Regex::builder().concat_marker('·').build_from(re);
+
+        let regex = build("a·b|c·(d·e)*").unwrap();
+        assert_eq!(
+            regex.ast(),
+            &Node::Alt(vec![
+                Node::Concat(vec![Node::Char('a'), Node::Char('b')]),
+                Node::Concat(vec![
+                    Node::Char('c'),
+                    Node::Repeat(Box::new(Node::Group(
+                        Box::new(Node::Concat(vec![Node::Char('d'), Node::Char('e')])),
+                        None,
+                    ))),
+                ]),
+            ])
+        );
+        assert!(regex.is_full_match("cdede"));
+
+        // 記号を使わない連接と混ぜて書け、エスケープした記号はその文字自体
+        assert_eq!(build("ab·c").unwrap().ast(), build("a·b·c").unwrap().ast());
+        assert!(build(r"a\·b").unwrap().is_full_match("a·b"));
+
+        // メタ文字を指定するとその意味は失われる
+        let regex = Regex::builder()
+            .concat_marker('.')
+            .build_from(r"a.b\.")
+            .unwrap();
+        assert!(regex.is_full_match("ab."));
+        assert!(!regex.is_full_match("axb."));
+
+        for invalid in ["a·", "·a", "a·|b", "(a·)"] {
+            assert!(build(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn scoped_case_insensitive_flag() {
+        let regex = Regex::new("a(?i:b)c").unwrap();
+        assert!(regex.is_full_match("abc"));
+        assert!(regex.is_full_match("aBc"));
+        assert!(!regex.is_full_match("Abc"));
+        assert!(!regex.is_full_match("abC"));
+
+        // 括弧の中で `-i` により無効にでき、括弧の後は元の指定に戻る
+        let regex = Regex::new("(?i:a(?-i:b)c)d").unwrap();
+        assert!(regex.is_full_match("AbCd"));
+        assert!(!regex.is_full_match("ABcd"));
+        assert!(!regex.is_full_match("abcD"));
+
+        // 大文字・小文字の無い文字や `.` はそのまま
+        let regex = Regex::new("(?i:x1.)+").unwrap();
+        assert!(regex.is_full_match("X1?x1!"));
+        assert!(!regex.is_full_match("x2a"));
+
+        // 表示すると大文字と小文字の選択になり、そのまま読み直せる
+        let ast = Node::parse(&lexer::tokenize("a(?i:bc)*").unwrap()).unwrap();
+        assert_eq!(ast.to_string(), "a(?:(?:b|B)(?:c|C))*");
+        assert_eq!(
+            Node::parse(&lexer::tokenize(&ast.to_string()).unwrap()),
+            Ok(ast)
+        );
+        assert!(Regex::new("(?i:é)").unwrap().is_full_match("É"));
+
+        assert!(Regex::new("(?x:a)").is_err());
+        assert!(Regex::new("(?i-").is_err());
+    }
 }
@@ -0,0 +1,274 @@
+use std::ops::Range;
+
+use crate::parser::{Look, Node};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Inst {
+    Char(char),
+    Any,
+    Look(Look),
+    Split(usize, usize), // 前者を優先して両方へ分岐
+    Jmp(usize),
+    Save(usize), // 現在位置を slot に記録
+    Match,
+}
+
+// Pike VM 用に AST をコンパイルした命令列
+// slot 2i, 2i+1 がグループ i の開始・終了位置を表す (グループ 0 は一致全体)
+#[derive(Debug, Clone)]
+pub(crate) struct Program {
+    insts: Vec<Inst>,
+    groups: usize,
+    captures: bool,
+}
+
+impl Program {
+    // captures が false の場合、`(...)` もグループとして記録せず一致全体 (グループ 0) のみを記録する
+    pub(crate) fn new(value: &Node, captures: bool) -> Self {
+        let mut program = Self {
+            insts: Vec::new(),
+            groups: 1,
+            captures,
+        };
+
+        program.insts.push(Inst::Save(0));
+        program.compile(value);
+        program.insts.push(Inst::Save(1));
+        program.insts.push(Inst::Match);
+
+        program
+    }
+
+    // グループ 0 を含むグループの数
+    pub(crate) fn num_groups(&self) -> usize {
+        self.groups
+    }
+
+    fn compile(&mut self, n: &Node) {
+        match n {
+            Node::Empty => {}
+            Node::Char(c) => self.insts.push(Inst::Char(*c)),
+            Node::Any => self.insts.push(Inst::Any),
+            Node::Look(l) => self.insts.push(Inst::Look(*l)),
+            Node::Concat(items) => items.iter().for_each(|n| self.compile(n)),
+            Node::Alt(branches) => {
+                // split L1, L2; L1: n1; jmp END; L2: split L3, L4; L3: n2; jmp END; ... L4: nk; END:
+                let mut jmps = Vec::new();
+
+                for (i, b) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.push_placeholder();
+                        self.compile(b);
+                        jmps.push(self.push_placeholder());
+
+                        self.insts[split] = Inst::Split(split + 1, self.insts.len());
+                    } else {
+                        self.compile(b);
+                    }
+                }
+
+                let end = self.insts.len();
+                for jmp in jmps {
+                    self.insts[jmp] = Inst::Jmp(end);
+                }
+            }
+            Node::Repeat(n) => {
+                // L1: split L2, L3; L2: n; jmp L1; L3:
+                let split = self.push_placeholder();
+                self.compile(n);
+                self.insts.push(Inst::Jmp(split));
+                let l3 = self.insts.len();
+
+                self.insts[split] = Inst::Split(split + 1, l3);
+            }
+            Node::Plus(n) => {
+                // L1: n; split L1, L2; L2:
+                let l1 = self.insts.len();
+                self.compile(n);
+                let split = self.insts.len();
+
+                self.insts.push(Inst::Split(l1, split + 1));
+            }
+            Node::Optional(n) => {
+                // split L1, L2; L1: n; L2:
+                let split = self.push_placeholder();
+                self.compile(n);
+                let l2 = self.insts.len();
+
+                self.insts[split] = Inst::Split(split + 1, l2);
+            }
+            Node::Group(n, _) if !self.captures => self.compile(n),
+            Node::Group(n, _) => {
+                let group = self.groups;
+                self.groups += 1;
+
+                self.insts.push(Inst::Save(group * 2));
+                self.compile(n);
+                self.insts.push(Inst::Save(group * 2 + 1));
+            }
+        }
+    }
+
+    fn push_placeholder(&mut self) -> usize {
+        self.insts.push(Inst::Jmp(0));
+
+        self.insts.len() - 1
+    }
+
+    // haystack[start..end] 全体に一致するときの各グループの範囲を返す
+    // 複数の一致の仕方がある場合は、分岐で前にある方 (繰り返しでは繰り返す方) を優先する
+    pub(crate) fn exec(
+        &self,
+        haystack: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<Vec<Option<Range<usize>>>> {
+        let mut clist = Threads::new(self.insts.len());
+        let mut nlist = Threads::new(self.insts.len());
+
+        self.add_thread(&mut clist, haystack, 0, start, vec![None; self.groups * 2]);
+
+        for (i, c) in haystack[start..end].char_indices() {
+            let at = start + i + c.len_utf8();
+
+            for (pc, slots) in clist.threads.drain(..) {
+                if matches!(self.insts[pc], Inst::Any) || self.insts[pc] == Inst::Char(c) {
+                    self.add_thread(&mut nlist, haystack, pc + 1, at, slots);
+                }
+            }
+
+            std::mem::swap(&mut clist, &mut nlist);
+            nlist.clear();
+        }
+
+        // 優先度の高い順に並んでいるため、最初に見つかった Match のスレッドを採用する
+        let (_, slots) = clist
+            .threads
+            .into_iter()
+            .find(|(pc, _)| self.insts[*pc] == Inst::Match)?;
+
+        Some(self.groups_of(&slots))
+    }
+
+    // haystack[start..] の先頭から始まる一致のうち、優先度の最も高いものの各グループの範囲を返す
+    // Match に達したスレッドより優先度の低いスレッドはその時点で捨て、高いスレッドのみ続ける
+    pub(crate) fn exec_prefix(
+        &self,
+        haystack: &str,
+        start: usize,
+    ) -> Option<Vec<Option<Range<usize>>>> {
+        let mut clist = Threads::new(self.insts.len());
+        let mut nlist = Threads::new(self.insts.len());
+        let mut matched = None;
+
+        self.add_thread(&mut clist, haystack, 0, start, vec![None; self.groups * 2]);
+
+        let mut chars = haystack[start..].char_indices();
+
+        loop {
+            let next = chars.next();
+
+            for (pc, slots) in clist.threads.drain(..) {
+                match (self.insts[pc], next) {
+                    (Inst::Match, _) => {
+                        matched = Some(slots);
+                        break;
+                    }
+                    (Inst::Any, Some((i, c))) => {
+                        self.add_thread(
+                            &mut nlist,
+                            haystack,
+                            pc + 1,
+                            start + i + c.len_utf8(),
+                            slots,
+                        );
+                    }
+                    (Inst::Char(x), Some((i, c))) if x == c => {
+                        self.add_thread(
+                            &mut nlist,
+                            haystack,
+                            pc + 1,
+                            start + i + c.len_utf8(),
+                            slots,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+
+            if nlist.threads.is_empty() {
+                break;
+            }
+
+            std::mem::swap(&mut clist, &mut nlist);
+            nlist.clear();
+        }
+
+        matched.map(|slots| self.groups_of(&slots))
+    }
+
+    fn groups_of(&self, slots: &[Option<usize>]) -> Vec<Option<Range<usize>>> {
+        (0..self.groups)
+            .map(|g| match (slots[g * 2], slots[g * 2 + 1]) {
+                (Some(s), Some(e)) => Some(s..e),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn add_thread(
+        &self,
+        list: &mut Threads,
+        haystack: &str,
+        pc: usize,
+        at: usize,
+        mut slots: Vec<Option<usize>>,
+    ) {
+        if list.visited[pc] {
+            return;
+        }
+        list.visited[pc] = true;
+
+        match self.insts[pc] {
+            Inst::Jmp(x) => self.add_thread(list, haystack, x, at, slots),
+            Inst::Split(x, y) => {
+                self.add_thread(list, haystack, x, at, slots.clone());
+                self.add_thread(list, haystack, y, at, slots);
+            }
+            Inst::Save(n) => {
+                slots[n] = Some(at);
+                self.add_thread(list, haystack, pc + 1, at, slots);
+            }
+            Inst::Look(l) => {
+                let holds = match l {
+                    Look::StartText => at == 0,
+                    Look::EndText => at == haystack.len(),
+                };
+
+                if holds {
+                    self.add_thread(list, haystack, pc + 1, at, slots);
+                }
+            }
+            Inst::Char(_) | Inst::Any | Inst::Match => list.threads.push((pc, slots)),
+        }
+    }
+}
+
+struct Threads {
+    visited: Vec<bool>,
+    threads: Vec<(usize, Vec<Option<usize>>)>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self {
+            visited: vec![false; len],
+            threads: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.visited.iter_mut().for_each(|v| *v = false);
+        self.threads.clear();
+    }
+}
@@ -0,0 +1,274 @@
+use std::ops::Range;
+
+use crate::{captures::Captures, parser::Node, pikevm::Program, regex::Regex};
+
+// 部分式ごとにあらかじめコンパイルした Program と、その中のグループの範囲の決め方
+// 一致の範囲を探すたびに Program を作り直さないよう、AST から一度だけ作る
+enum Plan {
+    // グループを含まない部分式 (範囲を決めるものが無い)
+    Leaf,
+    Group(Box<Plan>),
+    Concat(Vec<ConcatItem>),
+    // 各分岐の Program、計画、含まれるグループの数
+    Alt(Vec<(Program, Plan, usize)>),
+    Optional(Program, Box<Plan>),
+    Repeat {
+        inner: Program,
+        // inner の0回以上の繰り返し
+        star: Program,
+        plan: Box<Plan>,
+        groups: usize,
+        plus: bool,
+    },
+}
+
+struct ConcatItem {
+    // この要素と、その後の残りの要素の連接の Program (最後の要素には無い)
+    split: Option<(Program, Program)>,
+    plan: Plan,
+    groups: usize,
+}
+
+impl Plan {
+    fn new(n: &Node) -> Self {
+        if n.groups() == 0 {
+            return Self::Leaf;
+        }
+
+        match n {
+            Node::Empty | Node::Char(_) | Node::Any | Node::Look(_) => Self::Leaf,
+            Node::Group(inner, _) => Self::Group(Box::new(Self::new(inner))),
+            Node::Concat(items) => Self::Concat(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| ConcatItem {
+                        split: (i + 1 < items.len()).then(|| {
+                            (
+                                Program::new(item, false),
+                                Program::new(&Node::Concat(items[i + 1..].to_vec()), false),
+                            )
+                        }),
+                        plan: Self::new(item),
+                        groups: item.groups(),
+                    })
+                    .collect(),
+            ),
+            Node::Alt(branches) => Self::Alt(
+                branches
+                    .iter()
+                    .map(|b| (Program::new(b, false), Self::new(b), b.groups()))
+                    .collect(),
+            ),
+            Node::Optional(inner) => {
+                Self::Optional(Program::new(inner, false), Box::new(Self::new(inner)))
+            }
+            Node::Repeat(inner) | Node::Plus(inner) => Self::Repeat {
+                inner: Program::new(inner, false),
+                star: Program::new(&Node::Repeat(inner.clone()), false),
+                plan: Box::new(Self::new(inner)),
+                groups: inner.groups(),
+                plus: matches!(n, Node::Plus(_)),
+            },
+        }
+    }
+}
+
+// 一致全体の範囲が決まった後、各部分式が POSIX の規則 (前にある部分式ほど長く一致させる) に従う範囲を決める
+struct Posix<'h> {
+    haystack: &'h str,
+    groups: Vec<Option<Range<usize>>>,
+}
+
+impl Posix<'_> {
+    // program が haystack[start..end] 全体に一致するか
+    fn matches_span(&self, program: &Program, start: usize, end: usize) -> bool {
+        program.exec(self.haystack, start, end).is_some()
+    }
+
+    // start..=end の文字の境界を後ろから順に
+    fn ends(&self, start: usize, end: usize) -> impl Iterator<Item = usize> + use<> {
+        let mut ends: Vec<usize> = self.haystack[start..end]
+            .char_indices()
+            .map(|(i, _)| start + i)
+            .chain(std::iter::once(end))
+            .collect();
+        ends.reverse();
+
+        ends.into_iter()
+    }
+
+    // plan の部分式が haystack[start..end] 全体に一致するとき、その中のグループの範囲を決める
+    // base は部分式の中で最初に現れるグループの番号
+    fn assign(&mut self, plan: &Plan, start: usize, end: usize, base: usize) {
+        match plan {
+            Plan::Leaf => {}
+            Plan::Group(inner) => {
+                self.groups[base] = Some(start..end);
+                self.assign(inner, start, end, base + 1);
+            }
+            Plan::Concat(items) => {
+                let mut at = start;
+                let mut base = base;
+
+                for item in items {
+                    // 残りが一致できる範囲で、この要素をできるだけ長く一致させる
+                    let m = match &item.split {
+                        None => end,
+                        Some((this, rest)) => self
+                            .ends(at, end)
+                            .find(|m| {
+                                self.matches_span(this, at, *m) && self.matches_span(rest, *m, end)
+                            })
+                            .unwrap_or(end),
+                    };
+
+                    self.assign(&item.plan, at, m, base);
+
+                    at = m;
+                    base += item.groups;
+                }
+            }
+            Plan::Alt(branches) => {
+                let mut base = base;
+
+                for (program, plan, groups) in branches {
+                    if self.matches_span(program, start, end) {
+                        self.assign(plan, start, end, base);
+                        return;
+                    }
+
+                    base += groups;
+                }
+            }
+            Plan::Optional(program, inner) => {
+                if self.matches_span(program, start, end) {
+                    self.assign(inner, start, end, base);
+                }
+            }
+            Plan::Repeat {
+                plan, plus: true, ..
+            } if start == end => self.assign(plan, start, end, base),
+            Plan::Repeat {
+                inner,
+                star,
+                plan,
+                groups,
+                ..
+            } => {
+                let mut at = start;
+
+                // 各回をできるだけ長く一致させ、グループには最後の回の範囲を残す
+                while at < end {
+                    let Some(m) = self.ends(at, end).filter(|m| *m > at).find(|m| {
+                        self.matches_span(inner, at, *m) && self.matches_span(star, *m, end)
+                    }) else {
+                        break;
+                    };
+
+                    self.groups[base..base + groups].fill(None);
+                    self.assign(plan, at, m, base);
+
+                    at = m;
+                }
+            }
+        }
+    }
+}
+
+impl Node {
+    // ノードに含まれるグループの数
+    fn groups(&self) -> usize {
+        match self {
+            Self::Empty | Self::Char(_) | Self::Any | Self::Look(_) => 0,
+            Self::Concat(ns) | Self::Alt(ns) => ns.iter().map(Self::groups).sum(),
+            Self::Repeat(n) | Self::Plus(n) | Self::Optional(n) => n.groups(),
+            Self::Group(n, _) => 1 + n.groups(),
+        }
+    }
+}
+
+impl Regex {
+    /// `captures` と同じく最初の最長の一致について各グループの範囲を返すが、
+    /// 一致の仕方が複数ある場合は POSIX の規則に従い、前にある部分式ほど長く一致させる
+    /// 繰り返しの中のグループは最後の回の範囲になる
+    ///
+    /// 部分式ごとに区切りの候補を一致の長さだけ試し、その都度 NFA のシミュレーションで確かめるため、
+    /// 繰り返しがあると一致の長さの3乗以上に比例する時間がかかりうる
+    pub fn captures_posix<'h>(&self, haystack: &'h str) -> Option<Captures<'h>> {
+        let range = self.find(haystack)?.range();
+
+        if !self.options().is_captures() {
+            return Some(Captures::new(haystack, vec![Some(range)]));
+        }
+
+        let ast = self.ast();
+        let mut posix = Posix {
+            haystack,
+            groups: vec![None; 1 + ast.groups()],
+        };
+        posix.groups[0] = Some(range.clone());
+        posix.assign(&Plan::new(ast), range.start, range.end, 1);
+
+        Some(Captures::new(haystack, posix.groups))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    fn spans(re: &str, haystack: &str) -> Vec<Option<std::ops::Range<usize>>> {
+        let regex = Regex::builder().captures(true).build_from(re).unwrap();
+        let caps = regex.captures_posix(haystack).unwrap();
+
+        (0..caps.len())
+            .map(|i| caps.get(i).map(|m| m.range()))
+            .collect()
+    }
+
+    #[test]
+    fn posix_submatches() {
+        // 前の部分式ほど長く一致する
+        assert_eq!(
+            spans("(a*)(a*)", "aaa"),
+            vec![Some(0..3), Some(0..3), Some(3..3)]
+        );
+        assert_eq!(
+            spans("(a|ab)(c|bcd)(d*)", "abcd"),
+            vec![Some(0..4), Some(0..2), Some(2..3), Some(3..4)]
+        );
+
+        // 繰り返しの中のグループは最後の回
+        assert_eq!(
+            spans("((a)(b))+", "xabab"),
+            vec![Some(1..5), Some(3..5), Some(3..4), Some(4..5)]
+        );
+        assert_eq!(spans("(a|b)*c", "abc"), vec![Some(0..3), Some(1..2)]);
+        assert_eq!(spans("(a)*b", "b"), vec![Some(0..1), None]);
+    }
+
+    #[test]
+    fn posix_differs_from_priority_order() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(a|ab)(c|bcd)(d*)")
+            .unwrap();
+
+        // captures は分岐で左を優先する
+        let caps = regex.captures("abcd").unwrap();
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(0..1));
+        assert_eq!(caps.get(2).map(|m| m.range()), Some(1..4));
+
+        let caps = regex.captures_posix("abcd").unwrap();
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(0..2));
+        assert_eq!(caps.get(2).map(|m| m.range()), Some(2..3));
+
+        // グループを有効にしていない場合は一致全体のみ
+        let caps = Regex::new("(a*)(a*)")
+            .unwrap()
+            .captures_posix("aa")
+            .unwrap();
+        assert_eq!(caps.len(), 1);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{nfa::NfaTrans, regex::Regex};
+
+/// `Regex::match_profiled` で計測した、NFA のシミュレーションの統計
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchStats {
+    /// 各文字について、遷移を調べた状態の数の合計
+    pub state_steps: usize,
+    /// 同時に保持した状態の数の最大値 (大きいほどパターンが曖昧である目安になる)
+    pub max_states: usize,
+    /// 判定が終わるまでに読んだ文字数
+    pub chars_consumed: usize,
+}
+
+impl Regex {
+    /// 入力全体への一致を判定し、その過程の統計と共に返す
+    /// `anchored_default` の指定には従わず、常に入力全体への一致を判定する
+    /// DFA から作った正規表現 (`difference` など) ではその DFA で判定し、状態の数は常に1つとして数える
+    pub fn match_profiled(&self, input: &str) -> (bool, MatchStats) {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        let mut stats = MatchStats::default();
+
+        if let Some(dfa) = self.backend_dfa() {
+            let mut state = dfa.start();
+            stats.max_states = 1;
+
+            for c in input.chars() {
+                stats.state_steps += 1;
+                stats.chars_consumed += 1;
+
+                match dfa.next(&state, c) {
+                    Some(next) => state = next,
+                    None => return (false, stats),
+                }
+            }
+
+            return (dfa.is_accept(&state), stats);
+        }
+
+        let nfa = self.nfa();
+
+        let mut states = nfa.start_states(true);
+        stats.max_states = states.len();
+
+        for c in input.chars() {
+            stats.state_steps += states.len();
+            stats.chars_consumed += 1;
+
+            states = nfa.states_next(&states, &NfaTrans::Char(c));
+            stats.max_states = stats.max_states.max(states.len());
+
+            if states.is_empty() {
+                return (false, stats);
+            }
+        }
+
+        (nfa.is_accepting(&states, input.is_empty(), true), stats)
+    }
+
+    /// `is_full_match(input)` を `iterations` 回繰り返し、かかった時間の合計を返す
+    /// ベンチマークのフレームワークを使わずにパターンと入力ごとの速さを比べるための簡易的なもので、
+    /// 最適化で判定が省かれないよう結果は `std::hint::black_box` に渡す
+    #[cfg(feature = "std")]
+    pub fn bench_match(&self, input: &str, iterations: usize) -> std::time::Duration {
+        let start = std::time::Instant::now();
+
+        for _ in 0..iterations {
+            std::hint::black_box(self.is_full_match(std::hint::black_box(input)));
+        }
+
+        start.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn profiled_state_count_is_bounded() {
+        let regex = Regex::new("(a|a)*").unwrap();
+
+        let (matched, stats) = regex.match_profiled("aaaa");
+        assert!(matched);
+        assert_eq!(stats.chars_consumed, 4);
+
+        // 入力が長くなっても同時に保持する状態の数は NFA の状態数を超えない
+        let (_, longer) = regex.match_profiled(&"a".repeat(100));
+        assert_eq!(longer.max_states, stats.max_states);
+        assert!(stats.max_states <= regex.nfa().num_states());
+        assert!(stats.state_steps <= stats.max_states * 4);
+
+        let (matched, stats) = regex.match_profiled("aab");
+        assert!(!matched);
+        assert_eq!(stats.chars_consumed, 3);
+        assert_eq!(regex.is_full_match("aab"), matched);
+
+        // DFA で判定する正規表現では状態は常に1つ
+        let not_a = Regex::new(".")
+            .unwrap()
+            .difference(&Regex::new("a").unwrap());
+        let (matched, stats) = not_a.match_profiled("b");
+        assert!(matched);
+        assert_eq!((stats.max_states, stats.chars_consumed), (1, 1));
+        assert!(!not_a.match_profiled("a").0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn bench_match_measures_time() {
+        let regex = Regex::new("a(b|c)*d").unwrap();
+        let input = format!("a{}d", "bc".repeat(10));
+
+        assert!(!regex.bench_match(&input, 100).is_zero());
+        assert!(regex.bench_match(&input, 0) < regex.bench_match(&input, 100));
+    }
+}
@@ -1,13 +1,32 @@
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    ops::Range,
+    sync::OnceLock,
+};
 
 use crate::{
-    lexer::{self, TokenKind},
-    nfa::{Nfa, NfaState, NfaTrans},
-    parser::{Node, ParseError},
+    builder::{Anchored, MatchKind, RegexBuilder},
+    captures::{Captures, Match, OwnedCaptures},
+    dfa::{Dfa, DfaState},
+    lexer::{self, LexError, TokenKind},
+    matcher::Matcher,
+    nfa::{Nfa, NfaState, NfaTrans, Symbol},
+    optimizer,
+    parser::{Look, Node, ParseError},
+    pikevm::Program,
 };
 
-impl Nfa {
-    fn states_next(&self, states: &HashSet<NfaState>, trans: &NfaTrans) -> HashSet<NfaState> {
+#[cfg(feature = "internals")]
+use crate::nfa::NfaBfs;
+
+impl<S: Symbol> Nfa<S> {
+    pub(crate) fn states_next(
+        &self,
+        states: &HashSet<NfaState>,
+        trans: &NfaTrans<S>,
+    ) -> HashSet<NfaState> {
         let mut nexts = HashSet::new();
 
         for s in states.iter() {
@@ -17,37 +36,117 @@ impl Nfa {
         nexts
     }
 
-    fn next(&self, state: &NfaState, trans: &NfaTrans) -> HashSet<NfaState> {
+    fn next(&self, state: &NfaState, trans: &NfaTrans<S>) -> HashSet<NfaState> {
         // 現在の状態 state から epsilon遷移で到達可能な状態の集合 e_starts を取得
         let mut starts = HashSet::new();
         starts.insert(state.to_owned());
-        let e_starts = self.epsilon_next(starts);
+        let e_starts = self.epsilon_next(starts, false, false);
 
         // 指定された遷移がepsilon遷移の場合、e_starts が求める状態の集合であるため直ちに終了
         if trans == &NfaTrans::Epsilon {
             e_starts
         } else {
             // 指定された遷移がepsilon遷移でない場合、e_starts からそのように遷移した集合 nexts を取得
+            // 文字による遷移の場合は任意の1文字 (`.`) の遷移も含める
             let mut nexts = HashSet::new();
             for s in e_starts.iter() {
-                if let Some(transs) = self.states().get(s)
-                    && let Some(t_nexts) = transs.get(trans)
-                {
-                    nexts.extend(t_nexts);
+                if let Some(transs) = self.states().get(s) {
+                    if let Some(t_nexts) = transs.get(trans) {
+                        nexts.extend(t_nexts);
+                    }
+
+                    if matches!(trans, NfaTrans::Char(_))
+                        && let Some(any_nexts) = transs.get(&NfaTrans::Any)
+                    {
+                        nexts.extend(any_nexts);
+                    }
                 }
             }
 
             // nexts からepsilon遷移して得られる集合が求める集合
-            self.epsilon_next(nexts)
+            self.epsilon_next(nexts, false, false)
+        }
+    }
+
+    // 入力の先頭での状態の集合。at_start が真のときは文字列の先頭として `\A` も通過する
+    pub(crate) fn start_states(&self, at_start: bool) -> HashSet<NfaState> {
+        let mut starts = HashSet::new();
+        starts.insert(self.start());
+
+        self.epsilon_next(starts, at_start, false)
+    }
+
+    // 入力をここで終えたときに受理するか。at_end が真のときは文字列の終端として `\z` も通過する
+    pub(crate) fn is_accepting(
+        &self,
+        states: &HashSet<NfaState>,
+        at_start: bool,
+        at_end: bool,
+    ) -> bool {
+        if at_end {
+            self.epsilon_next(states.clone(), at_start, true)
+                .contains(&self.accept())
+        } else {
+            states.contains(&self.accept())
+        }
+    }
+
+    // 受理状態に到達しうる状態の集合。先頭より後の位置にいるものとして `\A` の遷移は通過せず、
+    // `\z` の遷移の後では文字による遷移を通過しない (`a\zb` で "a" の後の状態は含まれない)
+    pub(crate) fn live_states(&self) -> HashSet<NfaState> {
+        let mut rev: HashMap<NfaState, Vec<(NfaState, &NfaTrans<S>)>> = HashMap::new();
+
+        for (from, transs) in self.states().iter() {
+            for (trans, tos) in transs.iter() {
+                for to in tos.iter() {
+                    rev.entry(*to).or_default().push((*from, trans));
+                }
+            }
+        }
+
+        // まず epsilon遷移と `\z` のみで受理状態に到達できる状態を求め、
+        // 次にそれらへ epsilon遷移と文字による遷移で到達できる状態を加える
+        let at_end = Self::reach_back(&rev, HashSet::from([self.accept()]), |t| {
+            matches!(t, NfaTrans::Epsilon | NfaTrans::Look(Look::EndText))
+        });
+
+        Self::reach_back(&rev, at_end, |t| {
+            matches!(t, NfaTrans::Epsilon | NfaTrans::Char(_) | NfaTrans::Any)
+        })
+    }
+
+    fn reach_back(
+        rev: &HashMap<NfaState, Vec<(NfaState, &NfaTrans<S>)>>,
+        init: HashSet<NfaState>,
+        admits: impl Fn(&NfaTrans<S>) -> bool,
+    ) -> HashSet<NfaState> {
+        let mut reached = init;
+        let mut stack: Vec<NfaState> = reached.iter().copied().collect();
+
+        while let Some(s) = stack.pop() {
+            for (from, trans) in rev.get(&s).into_iter().flatten() {
+                if admits(trans) && reached.insert(*from) {
+                    stack.push(*from);
+                }
+            }
         }
+
+        reached
     }
 
-    fn epsilon_next(&self, states: HashSet<NfaState>) -> HashSet<NfaState> {
+    // epsilon遷移と、現在の位置で成り立つ `\A` (at_start) / `\z` (at_end) の遷移で到達可能な状態の集合
+    // 文字による遷移の直後は次の文字の有無が分からないため、at_end は偽として扱い、受理の判定時に改めて通過させる
+    pub(crate) fn epsilon_next(
+        &self,
+        states: HashSet<NfaState>,
+        at_start: bool,
+        at_end: bool,
+    ) -> HashSet<NfaState> {
         let mut nexts = states.clone();
         let mut new = states;
 
         while !new.is_empty() {
-            let next_new = self.transit_epsilon(&new);
+            let next_new = self.transit_epsilon(&new, at_start, at_end);
             new = next_new.difference(&nexts).map(|n| n.to_owned()).collect();
             nexts.extend(next_new);
         }
@@ -55,14 +154,27 @@ impl Nfa {
         nexts
     }
 
-    fn transit_epsilon(&self, states: &HashSet<NfaState>) -> HashSet<NfaState> {
+    fn transit_epsilon(
+        &self,
+        states: &HashSet<NfaState>,
+        at_start: bool,
+        at_end: bool,
+    ) -> HashSet<NfaState> {
         let mut nexts = HashSet::new();
 
         for s in states.iter() {
-            if let Some(transs) = self.states().get(s)
-                && let Some(epsilon_nexts) = transs.get(&NfaTrans::Epsilon)
-            {
-                nexts.extend(epsilon_nexts);
+            if let Some(transs) = self.states().get(s) {
+                if let Some(epsilon_nexts) = transs.get(&NfaTrans::Epsilon) {
+                    nexts.extend(epsilon_nexts);
+                }
+
+                if at_start && let Some(look_nexts) = transs.get(&NfaTrans::Look(Look::StartText)) {
+                    nexts.extend(look_nexts);
+                }
+
+                if at_end && let Some(look_nexts) = transs.get(&NfaTrans::Look(Look::EndText)) {
+                    nexts.extend(look_nexts);
+                }
             }
         }
 
@@ -70,9 +182,59 @@ impl Nfa {
     }
 }
 
-#[derive(Debug, Clone)]
+impl Nfa {
+    // 一致の最初の文字になりうる文字の集合。空文字列に一致しうる場合と `.` で始まりうる場合は None
+    // `\A` と `\z` はどちらも通過できるとして求めるため、どの位置から始まる一致についても取りこぼしは無い
+    pub(crate) fn first_chars(&self) -> Option<BTreeSet<char>> {
+        let states = self.epsilon_next(HashSet::from([self.start()]), true, true);
+
+        if states.contains(&self.accept()) {
+            return None;
+        }
+
+        let mut chars = BTreeSet::new();
+
+        for transs in states.iter().filter_map(|s| self.states().get(s)) {
+            for t in transs.keys() {
+                match t {
+                    NfaTrans::Char(c) => {
+                        chars.insert(*c);
+                    }
+                    NfaTrans::Any => return None,
+                    NfaTrans::Epsilon | NfaTrans::Look(_) => {}
+                }
+            }
+        }
+
+        Some(chars)
+    }
+}
+
+// 一致の判定に使うオートマトン
+#[derive(Debug, Clone, Copy)]
+enum Backend {
+    // パターンから作った NFA (グループと leftmost-first の一致は Pike VM)
+    Nfa,
+    // DFA の演算の結果などの `dfa` に入っている DFA。グループと分岐の優先順位を持たない
+    Dfa,
+}
+
+#[derive(Clone)]
 pub struct Regex {
-    nfa: Nfa,
+    pattern: Option<String>, // 構築に使ったパターン (DFA から作った場合は None)
+    // DFA から作った場合、AST は必要になったときに状態除去法で求め、パターンで表せない場合は None とする
+    // NFA と Pike VM の命令列も AST から必要になったときに作る
+    ast: OnceLock<Option<Node>>,
+    nfa: OnceLock<Nfa>,
+    program: OnceLock<Program>,
+    len_bounds: (usize, Option<usize>), // 一致する文字列の文字数の最小値と最大値
+    first_chars: Option<BTreeSet<char>>, // 一致の最初の文字になりうる文字 (None の場合は絞り込まない)
+    builder: RegexBuilder, // 構築時の設定 (repeated などで新しい Regex を作るときに引き継ぐ)
+    dfa: OnceLock<Dfa>,    // 部分集合構成法は時間がかかるため、必要になったときに一度だけ作る
+    reversed_nfa: OnceLock<Nfa>, // 逆順にしたパターンの NFA (右からの探索で必要になったときに作る)
+    live_states: OnceLock<HashSet<NfaState>>, // 受理状態に到達しうる NFA の状態 (`matcher` で Dead の判定に使う)
+    live_dfa_states: OnceLock<HashSet<DfaState>>, // DFA で判定する場合の、受理状態に到達しうる DFA の状態
+    backend: Backend,
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +242,24 @@ pub enum RegexParseError {
     UnexpectedEOF,
     UnexpectedToken(TokenKind, Vec<TokenKind>),
     ExpectedEOF(TokenKind),
+    TooDeeplyNested(usize),
+    InvalidRepetition(u32, u32),
+    TooLarge(usize),
+    InvalidEscape(String),
+    InvalidCodePoint(u32),
+    UnescapedWhitespace(char, usize),
+    EmptyGroupName,
+    DuplicateGroupName(String),
+}
+
+impl From<LexError> for RegexParseError {
+    fn from(value: LexError) -> Self {
+        match value {
+            LexError::InvalidEscape(escape) => Self::InvalidEscape(escape),
+            LexError::InvalidCodePoint(value) => Self::InvalidCodePoint(value),
+            LexError::UnescapedWhitespace(c, offset) => Self::UnescapedWhitespace(c, offset),
+        }
+    }
 }
 
 impl From<ParseError> for RegexParseError {
@@ -88,6 +268,11 @@ impl From<ParseError> for RegexParseError {
             ParseError::UnexpectedEOF => Self::UnexpectedEOF,
             ParseError::UnexpectedToken(t, expected) => Self::UnexpectedToken(t, expected),
             ParseError::ExpectedEOF(t) => Self::ExpectedEOF(t),
+            ParseError::TooDeeplyNested(max) => Self::TooDeeplyNested(max),
+            ParseError::InvalidRepetition(min, max) => Self::InvalidRepetition(min, max),
+            ParseError::TooLarge(limit) => Self::TooLarge(limit),
+            ParseError::EmptyGroupName => Self::EmptyGroupName,
+            ParseError::DuplicateGroupName(name) => Self::DuplicateGroupName(name),
         }
     }
 }
@@ -116,102 +301,2740 @@ impl Display for RegexParseError {
                 f,
                 "failed to parse regex, unexpected token found {t} but expected `EOF`"
             ),
+            Self::TooDeeplyNested(max) => write!(
+                f,
+                "failed to parse regex, groups are nested more than {max} levels deep"
+            ),
+            Self::InvalidRepetition(min, max) => write!(
+                f,
+                "failed to parse regex, invalid repetition `{{{min},{max}}}` whose minimum exceeds its maximum"
+            ),
+            Self::TooLarge(limit) => write!(
+                f,
+                "failed to compile regex, the expanded pattern exceeds the size limit of {limit}"
+            ),
+            Self::InvalidEscape(escape) => {
+                write!(
+                    f,
+                    "failed to parse regex, invalid escape sequence `{escape}`"
+                )
+            }
+            Self::InvalidCodePoint(value) => write!(
+                f,
+                "failed to parse regex, `{value:#X}` is not a Unicode scalar value"
+            ),
+            Self::UnescapedWhitespace(c, offset) => write!(
+                f,
+                "failed to parse regex, unescaped character {c:?} at position {offset}, write it as an escape such as `\\t` or `\\n`"
+            ),
+            Self::EmptyGroupName => write!(f, "failed to parse regex, the group name is empty"),
+            Self::DuplicateGroupName(name) => write!(
+                f,
+                "failed to parse regex, the group name `{name}` is used more than once"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegexParseError {}
+
+// NFA の遷移表全体は大きすぎるため、パターンと状態数のみを表示する
+// DFA から作った場合はパターンを求めずに DFA の状態数のみを表示する
+impl std::fmt::Debug for Regex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.pattern {
+            Some(pattern) => f
+                .debug_struct("Regex")
+                .field("pattern", pattern)
+                .field("nfa_states", &self.nfa().num_states())
+                .finish(),
+            None => f
+                .debug_struct("Regex")
+                .field("dfa_states", &self.dfa().num_states())
+                .finish(),
         }
     }
 }
 
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout;
+
+#[cfg(feature = "std")]
+impl Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "regex matching timed out")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Timeout {}
+
+/// `Regex::matches_with_state_limit` で、同時に辿る NFA の状態の数が上限を超えたことを表すエラー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooComplex {
+    /// 上限を超えた時点で同時に辿っていた状態の数
+    pub states: usize,
+}
+
+impl Display for TooComplex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "regex is too complex for the input, {} NFA states were live at once",
+            self.states
+        )
+    }
+}
+
+impl std::error::Error for TooComplex {}
+
 impl Regex {
     pub fn new(re: &str) -> Result<Self, RegexParseError> {
-        let tokens = lexer::tokenize(re);
-        let ast = Node::parse(&tokens).map_err(RegexParseError::from)?;
-        let nfa = Nfa::from(ast);
+        Self::build(re, &RegexBuilder::new())
+    }
 
-        Ok(Self { nfa })
+    pub fn builder() -> RegexBuilder {
+        RegexBuilder::new()
     }
 
-    pub fn matches(&self, pattern: &str) -> bool {
-        let mut states = HashSet::new();
-        states.insert(self.nfa.start());
+    /// すべてのパターンを `Regex::new` でコンパイルし、それぞれの結果をパターンと同じ順で返す
+    /// 途中のパターンがエラーになっても残りのパターンのコンパイルを続ける
+    pub fn try_compile_all(patterns: &[&str]) -> Vec<Result<Regex, RegexParseError>> {
+        patterns.iter().map(|re| Self::new(re)).collect()
+    }
 
-        if !pattern.is_empty() {
-            for c in pattern.chars() {
-                states = self.nfa.states_next(&states, &NfaTrans::Char(c));
+    /// `text` 中のすべてのメタ文字を `\` でエスケープし、`text` そのものに一致するパターンにする
+    pub fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
 
-                if states.is_empty() {
-                    return false;
-                }
+        for c in text.chars() {
+            if lexer::is_meta(c) {
+                escaped.push('\\');
             }
+            escaped.push(c);
+        }
+
+        escaped
+    }
+
+    /// `escape` と同じだが、メタ文字を含まない場合は複製せずにそのまま返す
+    pub fn quote_if_needed(text: &str) -> Cow<'_, str> {
+        if text.chars().any(lexer::is_meta) {
+            Cow::Owned(Self::escape(text))
         } else {
-            // pattern が空文字列のときは開始状態からepsilon遷移をさせる
-            states = self.nfa.epsilon_next(states);
+            Cow::Borrowed(text)
+        }
+    }
+
+    pub(crate) fn build(re: &str, builder: &RegexBuilder) -> Result<Self, RegexParseError> {
+        let tokens = lexer::tokenize_with(re, builder.marker(), builder.is_whitespace_allowed())
+            .map_err(RegexParseError::from)?;
+        let ast = Node::parse_with(&tokens, builder.is_case_insensitive())
+            .map_err(RegexParseError::from)?;
+
+        if ast.size() > builder.max_size() {
+            return Err(RegexParseError::TooLarge(builder.max_size()));
         }
 
-        states.contains(&self.nfa.accept())
+        Ok(Self {
+            pattern: Some(re.to_string()),
+            ..Self::from_node(ast, builder)
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::regex::Regex;
+    // パターンの文字列は AST から復元したものになる
+    pub(crate) fn from_node(mut ast: Node, builder: &RegexBuilder) -> Self {
+        #[cfg(feature = "unicode-normalization")]
+        if let Some(form) = builder.normalization() {
+            ast = ast.normalized(form);
+        }
 
-    #[test]
-    fn regex_works() {
-        let regex = Regex::new("a(b|c)*").unwrap();
+        if builder.is_optimize() {
+            ast = ast.factor_alternation();
+        }
+
+        let nfa = Nfa::from(ast.clone());
+        debug_assert_eq!(nfa.validate(), Ok(()));
+        let program = Program::new(&ast, builder.is_captures());
+        let len_bounds = ast.len_bounds();
+        let first_chars = nfa.first_chars();
 
-        assert!(regex.matches("a"));
-        assert!(regex.matches("ab"));
-        assert!(regex.matches("ac"));
-        assert!(!regex.matches("b"));
-        assert!(!regex.matches("bcb"));
-        assert!(regex.matches("acbbc"));
+        Self {
+            pattern: Some(ast.to_string()),
+            ast: OnceLock::from(Some(ast)),
+            nfa: OnceLock::from(nfa),
+            program: OnceLock::from(program),
+            len_bounds,
+            first_chars,
+            builder: builder.clone(),
+            dfa: OnceLock::new(),
+            reversed_nfa: OnceLock::new(),
+            live_states: OnceLock::new(),
+            live_dfa_states: OnceLock::new(),
+            backend: Backend::Nfa,
+        }
     }
 
-    #[test]
-    fn regex_works2() {
-        let regex = Regex::new("a*b*").unwrap();
+    // 一致を DFA で判定する正規表現を作る
+    // DFA から状態除去法でパターンを求めるのは時間がかかるため、AST や NFA を使う機能が呼ばれたときに初めて求める
+    pub(crate) fn with_dfa(dfa: Dfa, builder: &RegexBuilder) -> Self {
+        Self {
+            pattern: None,
+            ast: OnceLock::new(),
+            nfa: OnceLock::new(),
+            program: OnceLock::new(),
+            len_bounds: dfa.len_bounds(),
+            first_chars: dfa.first_chars(),
+            builder: builder.clone(),
+            dfa: OnceLock::from(dfa),
+            reversed_nfa: OnceLock::new(),
+            live_states: OnceLock::new(),
+            live_dfa_states: OnceLock::new(),
+            backend: Backend::Dfa,
+        }
+    }
 
-        assert!(regex.matches("")); // 空文字可
-        assert!(regex.matches("a"));
-        assert!(regex.matches("aa"));
-        assert!(regex.matches("b"));
-        assert!(regex.matches("bb"));
-        assert!(regex.matches("aaabbb"));
-        assert!(regex.matches("aaa"));
-        assert!(regex.matches("bbb"));
-        assert!(!regex.matches("abba")); // a* の後は b* のみ
-        assert!(!regex.matches("ba")); // b の後に a は不可
+    // `normalize_input` を指定した場合、正規化で変わる入力では正規化した入力での位置が元の入力での位置と対応しないため、
+    // 位置を返すメソッドは `normalize` で正規化済みの入力のみを受け付け、それ以外の入力では panic する
+    #[cfg_attr(not(feature = "unicode-normalization"), allow(unused_variables))]
+    pub(crate) fn assert_normalized(&self, haystack: &str) {
+        #[cfg(feature = "unicode-normalization")]
+        if let Some(form) = self.builder.normalization() {
+            assert!(
+                crate::normalize::is_normalized(haystack, form),
+                "a regex built with `normalize_input` only returns positions in an input normalized by `Regex::normalize`"
+            );
+        }
     }
 
-    #[test]
-    fn regex_works3() {
-        let regex = Regex::new("(ab)*|c").unwrap();
+    /// 入力全体 (先頭から末尾まで) がパターンに一致するかを返す
+    /// `anchored_default` の指定に関わらず常に入力全体への一致を判定する。入力の一部への一致は `is_match` を使う
+    pub fn is_full_match(&self, input: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
 
-        assert!(regex.matches("")); // (ab)* → 空文字 ok
-        assert!(regex.matches("ab"));
-        assert!(regex.matches("abab"));
-        assert!(regex.matches("ababab"));
-        assert!(regex.matches("c"));
-        assert!(!regex.matches("a")); // ab の途中
-        assert!(!regex.matches("b"));
-        assert!(!regex.matches("abc")); // 全体一致ではない
-        assert!(!regex.matches("cab")); // 全体一致ではない
-        assert!(!regex.matches("cc")); // c 1文字のみ
+        self.full_match(input)
     }
 
-    #[test]
-    fn regex_works4() {
-        let regex = Regex::new("a(bc)*d").unwrap();
+    /// 入力全体がパターンに一致するかを返す
+    /// ビルダーで `anchored_default(Anchored::No)` を指定した場合は `is_match` と同じく入力の一部への一致を判定する
+    #[deprecated(note = "use `is_full_match` (anchored at both ends) or `is_match` (unanchored)")]
+    pub fn matches(&self, pattern: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let pattern = &*self.normalize(pattern);
+        match self.builder.anchored().unwrap_or(Anchored::Yes) {
+            Anchored::Yes => self.full_match(pattern),
+            Anchored::No => self.find_at_with(pattern, 0, self.builder.kind()).is_some(),
+        }
+    }
+
+    /// `input` と、それを文字単位で逆順にした文字列の両方が `is_full_match` で一致するかを返す
+    /// 左右対称な形式の検査のための簡便なメソッドで、2回判定するだけである
+    pub fn matches_symmetric(&self, input: &str) -> bool {
+        self.is_full_match(input) && self.is_full_match(&input.chars().rev().collect::<String>())
+    }
+
+    /// 入力の先頭と末尾の空白を取り除いた残りの全体が `is_full_match` で一致するかを返す
+    /// 取り除くのは `str::trim` と同じく Unicode の White_Space 属性を持つ文字 (ASCII の空白・タブ・改行や全角の空白など) で、
+    /// 入力の途中の空白はそのまま判定する
+    pub fn matches_trimmed(&self, input: &str) -> bool {
+        self.is_full_match(input.trim())
+    }
+
+    /// 入力の一部がパターンに一致するかを返す
+    /// ビルダーで `anchored_default(Anchored::Yes)` を指定した場合は `is_full_match` と同じく入力全体への一致を判定する
+    pub fn is_match(&self, haystack: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let haystack = &*self.normalize(haystack);
+
+        match self.builder.anchored().unwrap_or(Anchored::No) {
+            Anchored::Yes => self.full_match(haystack),
+            Anchored::No => self
+                .find_at_with(haystack, 0, self.builder.kind())
+                .is_some(),
+        }
+    }
+
+    /// 一致する文字列の長さ (文字数) の最小値と最大値を返す。いくらでも長く一致できる場合、最大値は None
+    pub fn match_len_bounds(&self) -> (usize, Option<usize>) {
+        self.len_bounds
+    }
+
+    // 入力の文字数が一致する文字列の長さの範囲に入るか
+    // UTF-8 の1文字は 1 から 4 バイトのため、バイト数だけで決まる場合は文字数を数えない
+    fn len_in_bounds(&self, input: &str) -> bool {
+        let (min, max) = self.len_bounds;
+        let bytes = input.len();
+
+        if bytes < min || max.is_some_and(|max| bytes.div_ceil(4) > max) {
+            return false;
+        }
+
+        if bytes.div_ceil(4) >= min && max.is_none_or(|max| bytes <= max) {
+            return true;
+        }
+
+        let chars = input.chars().count();
+
+        chars >= min && max.is_none_or(|max| chars <= max)
+    }
+
+    fn full_match(&self, pattern: &str) -> bool {
+        if !self.len_in_bounds(pattern) || !self.can_start_at(pattern, 0) {
+            return false;
+        }
+
+        if let Backend::Dfa = self.backend {
+            return self.dfa().matches(pattern);
+        }
+
+        #[cfg(test)]
+        tests::FULL_SIMULATIONS.with(|n| n.set(n.get() + 1));
+
+        let mut states = self.nfa().start_states(true);
+
+        for c in pattern.chars() {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        // pattern が空文字列のときは文字列の先頭かつ終端として判定する
+        self.nfa().is_accepting(&states, pattern.is_empty(), true)
+    }
+
+    /// `is_full_match` と同じだが、ASCII の英字の大文字と小文字を区別しない
+    /// 入力の各 ASCII 英字について大文字・小文字の両方の遷移をたどるのみで、`char::to_lowercase` による Unicode の大文字小文字の変換は行わない
+    /// そのため `é` と `É` のような ASCII 以外の文字は区別して判定する
+    pub fn matches_ci_ascii(&self, input: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        if !self.len_in_bounds(input) {
+            return false;
+        }
+
+        if let Backend::Dfa = self.backend {
+            return self.dfa().matches_ci_ascii(input);
+        }
+
+        let mut states = self.nfa().start_states(true);
+
+        for c in input.chars() {
+            states = if c.is_ascii_alphabetic() {
+                let mut nexts = self
+                    .nfa()
+                    .states_next(&states, &NfaTrans::Char(c.to_ascii_lowercase()));
+                nexts.extend(
+                    self.nfa()
+                        .states_next(&states, &NfaTrans::Char(c.to_ascii_uppercase())),
+                );
+
+                nexts
+            } else {
+                self.nfa().states_next(&states, &NfaTrans::Char(c))
+            };
+
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        self.nfa().is_accepting(&states, input.is_empty(), true)
+    }
+
+    /// `is_full_match` と同じだが、文字列の代わりにデコーダなどが返す文字の列全体への一致を判定する
+    /// 列の途中で `Err` が得られた場合はそのエラーを返す
+    /// 一致しないことが確定した時点で読むのをやめるため、それより後の `Err` は返さず `Ok(false)` になる
+    /// ビルダーで `normalize_input` を指定した場合は、正規化のために列をすべて読んでから判定する
+    pub fn matches_try_chars<E, I: IntoIterator<Item = Result<char, E>>>(
+        &self,
+        chars: I,
+    ) -> Result<bool, E> {
+        #[cfg(feature = "unicode-normalization")]
+        if self.builder.normalization().is_some() {
+            let input = chars.into_iter().collect::<Result<String, E>>()?;
+
+            return Ok(self.is_full_match(&input));
+        }
+
+        if let Backend::Dfa = self.backend {
+            return self.dfa().matches_try_chars(chars);
+        }
+
+        let mut states = self.nfa().start_states(true);
+        let mut is_empty = true;
+
+        for c in chars {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c?));
+            is_empty = false;
+
+            if states.is_empty() {
+                return Ok(false);
+            }
+        }
+
+        Ok(self.nfa().is_accepting(&states, is_empty, true))
+    }
+
+    /// `haystack` 中で最も左から始まる一致のうち、最長のもの (ビルダーの `match_kind` による) の範囲を返す
+    pub fn find<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.find_at(haystack, 0)
+    }
+
+    /// `find` と同じ一致について、一致した部分の文字列を返す
+    pub fn find_str<'h>(&self, haystack: &'h str) -> Option<&'h str> {
+        self.find(haystack).map(|m| m.as_str())
+    }
+
+    /// バイトオフセット `start` 以降に始まる最初の一致の範囲を返す。各開始位置では最長の一致 (`find` と同じ) を返す
+    /// 保存した位置から探索を再開するためのもので、`\A` は `start` ではなく常に文字列の先頭を表す
+    /// `start` が文字の境界でない、または入力の長さを超える場合は panic する
+    pub fn find_at<'h>(&self, haystack: &'h str, start: usize) -> Option<Match<'h>> {
+        self.assert_normalized(haystack);
+        let range = self.find_at_with(haystack, start, self.builder.kind())?;
+
+        Some(Match::new(haystack, range))
+    }
+
+    pub(crate) fn find_at_with(
+        &self,
+        haystack: &str,
+        start: usize,
+        kind: MatchKind,
+    ) -> Option<Range<usize>> {
+        // 最初の文字になりえない位置からは一致を探さない
+        let mut starts = haystack[start..]
+            .char_indices()
+            .map(|(i, _)| start + i)
+            .chain(std::iter::once(haystack.len()))
+            .filter(|at| self.can_start_at(haystack, *at));
+
+        match (kind, self.backend) {
+            (MatchKind::LeftmostFirst, Backend::Nfa) => starts.find_map(|at| {
+                let groups = self.program().exec_prefix(haystack, at)?;
+
+                groups.into_iter().next().flatten()
+            }),
+            // DFA は分岐の優先順位を持たないため、常に最長の一致を返す
+            (MatchKind::LeftmostLongest, _) | (_, Backend::Dfa) => {
+                starts.find_map(|at| self.longest_match_at(haystack, at).map(|end| at..end))
+            }
+        }
+    }
+
+    /// 入力の先頭から始まる最長の一致のバイト長を返す。先頭から一致しない場合は None
+    /// 返す長さは常に文字の境界にあるため、`&input[n..]` で残りの入力を得られる
+    pub fn consume_prefix(&self, input: &str) -> Option<usize> {
+        self.assert_normalized(input);
+        self.longest_match_at(input, 0)
+    }
+
+    /// 入力の先頭から始まる最長の一致の文字数を返す。先頭から一致しない場合は None
+    /// `consume_prefix` がバイト長を返すのに対し、こちらは `char` の個数を返すため、
+    /// `input.chars().skip(n)` のように文字単位で入力を扱う場合に使う
+    pub fn match_prefix_len(&self, input: &str) -> Option<usize> {
+        self.consume_prefix(input)
+            .map(|len| input[..len].chars().count())
+    }
+
+    /// 先頭から一致する (接頭辞が一致する) 候補の添字を返す
+    pub fn matches_prefix_of_any(&self, candidates: &[&str]) -> Vec<usize> {
+        candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| {
+                #[cfg(feature = "unicode-normalization")]
+                let input = &self.normalize(input);
+
+                self.has_prefix_match(input)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // 最長の一致を探す longest_match_at と異なり、最初に受理した時点で終える
+    fn has_prefix_match(&self, input: &str) -> bool {
+        if let Backend::Dfa = self.backend {
+            return self.dfa().longest_match_at(input, 0).is_some();
+        }
+
+        let mut states = self.nfa().start_states(true);
+
+        if self.nfa().is_accepting(&states, true, input.is_empty()) {
+            return true;
+        }
+
+        for (i, c) in input.char_indices() {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+
+            if states.is_empty() {
+                return false;
+            }
+
+            if self
+                .nfa()
+                .is_accepting(&states, false, i + c.len_utf8() == input.len())
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // at から一致が始まりうるか。偽の場合 at の文字は一致の最初の文字になりえない (入力の終端では空の一致もない)
+    fn can_start_at(&self, haystack: &str, at: usize) -> bool {
+        match &self.first_chars {
+            None => true,
+            Some(chars) => haystack[at..]
+                .chars()
+                .next()
+                .is_some_and(|c| chars.contains(&c)),
+        }
+    }
+
+    // at から始まる最長の一致の終了位置を返す
+    pub(crate) fn longest_match_at(&self, haystack: &str, at: usize) -> Option<usize> {
+        #[cfg(test)]
+        tests::MATCH_STARTS.with(|starts| starts.borrow_mut().push(at));
+
+        if let Backend::Dfa = self.backend {
+            return self.dfa().longest_match_at(haystack, at);
+        }
+
+        let mut states = self.nfa().start_states(at == 0);
+
+        let mut end = self
+            .nfa()
+            .is_accepting(&states, at == 0, at == haystack.len())
+            .then_some(at);
+
+        for (i, c) in haystack[at..].char_indices() {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+
+            if states.is_empty() {
+                break;
+            }
+
+            let next = at + i + c.len_utf8();
+
+            if self
+                .nfa()
+                .is_accepting(&states, false, next == haystack.len())
+            {
+                end = Some(next);
+            }
+        }
+
+        end
+    }
+
+    /// 入力の先頭から始まる最長の一致の長さ (バイト数) と、入力をすべて読んだ後も次の文字を読める状態が残っているかを返す
+    /// 後者が真の場合、入力の後に文字が続けばより長く一致する可能性がある (続きの入力を待つかの判断に使う)
+    /// 入力の途中で一致の可能性が無くなった場合は偽になる
+    pub fn longest_match_info(&self, input: &str) -> (Option<usize>, bool) {
+        self.assert_normalized(input);
+        if let Backend::Dfa = self.backend {
+            return self.dfa().longest_prefix(input);
+        }
+
+        let mut states = self.nfa().start_states(true);
+        let mut longest = self
+            .nfa()
+            .is_accepting(&states, true, input.is_empty())
+            .then_some(0);
+
+        for (i, c) in input.char_indices() {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+
+            if states.is_empty() {
+                return (longest, false);
+            }
+
+            let next = i + c.len_utf8();
+
+            if self.nfa().is_accepting(&states, false, next == input.len()) {
+                longest = Some(next);
+            }
+        }
+
+        let alive = states
+            .iter()
+            .filter_map(|s| self.nfa().states().get(s))
+            .any(|transs| {
+                transs
+                    .keys()
+                    .any(|t| matches!(t, NfaTrans::Char(_) | NfaTrans::Any))
+            });
+
+        (longest, alive)
+    }
+
+    /// `find_iter` で得られる重ならない一致のうち、最も右のものの範囲を返す
+    pub fn find_last<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.find_iter(haystack).last()
+    }
+
+    /// 重ならない一致の範囲を左から順に返す。空の一致の後は1文字進めて探索を続ける
+    pub fn find_iter<'r, 'h>(&'r self, haystack: &'h str) -> FindIter<'r, 'h> {
+        self.assert_normalized(haystack);
+        FindIter {
+            regex: self,
+            haystack,
+            at: Some(0),
+        }
+    }
+
+    /// 重ならない一致の範囲を左から順に返す (`find_iter` と同じ)
+    ///
+    /// 空でない一致の後は、その終了位置から次の一致を探す (そこで空の一致が見つかることもある)
+    /// 空の一致の後は、その位置の次の文字 (1バイトではなく UTF-8 の1文字分) の境界から探す
+    /// 入力の終端での空の一致の後は終了する
+    /// そのため返す範囲の両端は常に文字の境界にあり、`&haystack[range]` で切り出せる
+    pub fn match_ranges_iter<'r, 'h>(&'r self, haystack: &'h str) -> FindIter<'r, 'h> {
+        self.find_iter(haystack)
+    }
+
+    /// 重なりを許してすべての一致の範囲を返す
+    /// `find_iter` は一致の終了位置から次を探すが、こちらは一致の開始位置の次の文字から探す
+    /// それぞれの開始位置では最長の一致のみを返す
+    pub fn find_iter_overlapping<'h>(
+        &self,
+        haystack: &'h str,
+    ) -> impl Iterator<Item = Match<'h>> + use<'_, 'h> {
+        self.assert_normalized(haystack);
+        let mut at = Some(0);
+
+        std::iter::from_fn(move || {
+            let range = self.find_at_with(haystack, at?, self.builder.kind())?;
+            let m = Match::new(haystack, range);
+
+            at = haystack[m.start()..]
+                .chars()
+                .next()
+                .map(|c| m.start() + c.len_utf8());
+
+            Some(m)
+        })
+    }
+
+    /// キャプチャするグループの数を返す。グループ 0 (一致全体) は数えない
+    /// `captures_read` で書き込まれる要素の数や `Captures::len` はこれに 1 を足した数になり、
+    /// `replace_all` の置換文字列では `$1` からこの数までを使える
+    /// ビルダーで `captures(true)` を指定しない場合、`(...)` はグループとならないため常に 0 になる
+    pub fn capture_count(&self) -> usize {
+        match self.backend {
+            Backend::Nfa => self.program().num_groups() - 1,
+            // DFA はグループを持たない
+            Backend::Dfa => 0,
+        }
+    }
+
+    /// 各グループの名前を、グループの番号を添字として返す。グループ 0 (一致全体) と名前の無いグループは None
+    /// 名前は `(?<name>...)` で付ける。要素の数は `capture_count` に 1 を足した数になる
+    pub fn capture_names(&self) -> Vec<Option<String>> {
+        let mut names = vec![None];
+
+        if self.builder.is_captures()
+            && let Backend::Nfa = self.backend
+        {
+            self.ast().group_names(&mut names);
+        }
+
+        names
+    }
+
+    /// 最初の一致について、各グループの範囲を返す
+    /// 一致の仕方が複数ある場合、`|` では左の分岐を、`*` ではより多く繰り返す方を優先する
+    /// ビルダーで `captures(true)` を指定しない場合、グループ 0 (一致全体) のみを返す
+    pub fn captures<'h>(&self, haystack: &'h str) -> Option<Captures<'h>> {
+        let m = self.find(haystack)?;
+
+        self.captures_in(haystack, m.range())
+    }
+
+    /// `captures` と同じ一致について、各グループの範囲を `slots` に書き込み、一致したかを返す
+    /// `slots` は最初に空にされ、一致した場合はグループの数だけ要素が入る (一致しなかった場合は空のまま)
+    /// 同じ `slots` を使い回すことで、呼び出しごとに結果の `Vec` を確保せずに済む
+    pub fn captures_read(&self, input: &str, slots: &mut Vec<Option<Range<usize>>>) -> bool {
+        slots.clear();
+
+        let Some(m) = self.find(input) else {
+            return false;
+        };
+
+        match self.groups_in(input, m.range()) {
+            Some(groups) => {
+                slots.extend(groups);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `captures` と同じだが、各グループに一致した文字列を複製して返す
+    pub fn captures_owned(&self, haystack: &str) -> Option<OwnedCaptures> {
+        self.captures(haystack).map(OwnedCaptures::from)
+    }
+
+    /// `is_full_match` と同じ判定を Pike VM で行い、一致した場合は各グループの範囲を返す
+    /// ビルダーで `anchored_default(Anchored::No)` を指定した場合は `captures` と同じく入力の一部への一致を判定する
+    /// `is_full_match` は状態集合のシミュレーションでグループを追跡しないが、こちらはスレッドごとにグループの位置を持つ
+    /// どちらも入力の長さに比例する時間で動作する
+    pub fn matches_with_captures_vm<'h>(&self, haystack: &'h str) -> Option<Captures<'h>> {
+        self.assert_normalized(haystack);
+        match self.builder.anchored().unwrap_or(Anchored::Yes) {
+            Anchored::Yes => self.captures_in(haystack, 0..haystack.len()),
+            Anchored::No => self.captures(haystack),
+        }
+    }
+
+    fn captures_in<'h>(&self, haystack: &'h str, range: Range<usize>) -> Option<Captures<'h>> {
+        let groups = self.groups_in(haystack, range)?;
+
+        Some(Captures::new(haystack, groups))
+    }
+
+    // haystack[range] 全体に一致する場合に、各グループの範囲を返す
+    fn groups_in(&self, haystack: &str, range: Range<usize>) -> Option<Vec<Option<Range<usize>>>> {
+        match self.backend {
+            Backend::Nfa => self.program().exec(haystack, range.start, range.end),
+            // DFA はグループを持たないため、一致全体のみを返す
+            Backend::Dfa => self
+                .dfa()
+                .matches(&haystack[range.clone()])
+                .then(|| vec![Some(range)]),
+        }
+    }
+
+    /// `find_iter` の一致を区切りとして入力を分割する。`str::split` と同じく、区切りが末尾にあれば最後に空文字列が入る
+    pub fn split<'h>(&self, haystack: &'h str) -> Vec<&'h str> {
+        let mut pieces = Vec::new();
+        let mut last = 0;
+
+        for m in self.find_iter(haystack) {
+            pieces.push(&haystack[last..m.start()]);
+            last = m.end();
+        }
+
+        pieces.push(&haystack[last..]);
+
+        pieces
+    }
+
+    /// `split` と同じだが、`str::split_terminator` と同じく末尾の空文字列を含めない
+    pub fn split_terminator<'h>(&self, haystack: &'h str) -> Vec<&'h str> {
+        let mut pieces = self.split(haystack);
+
+        if pieces.last() == Some(&"") {
+            pieces.pop();
+        }
+
+        pieces
+    }
+
+    /// 最初の一致 (`find` と同じ) の前と後の文字列を返す。一致した部分はどちらにも含めない
+    /// 一致しない場合は None を返す
+    pub fn split_at_first<'h>(&self, haystack: &'h str) -> Option<(&'h str, &'h str)> {
+        let m = self.find(haystack)?;
+
+        Some((&haystack[..m.start()], &haystack[m.end()..]))
+    }
+
+    /// すべての一致を `replacement` で置き換える
+    /// `replacement` 中の `$n` / `${n}` はグループ n に一致した文字列に、`$$` は `$` に置き換わる
+    pub fn replace_all(&self, haystack: &str, replacement: &str) -> String {
+        let mut replaced = String::new();
+        let mut last = 0;
+
+        for m in self.find_iter(haystack) {
+            replaced.push_str(&haystack[last..m.start()]);
+
+            if let Some(caps) = self.captures_in(haystack, m.range()) {
+                caps.expand(replacement, &mut replaced);
+            }
+
+            last = m.end();
+        }
+
+        replaced.push_str(&haystack[last..]);
+
+        replaced
+    }
+
+    /// すべての一致を、その一致を渡した `f` の返す文字列で置き換える
+    /// 一致は `find_iter` と同じ順に渡し、`replace_all` と異なり返した文字列中の `$` は展開しない
+    pub fn replace_all_with<F: FnMut(&Match) -> String>(&self, haystack: &str, mut f: F) -> String {
+        let mut replaced = String::new();
+        let mut last = 0;
+
+        for m in self.find_iter(haystack) {
+            replaced.push_str(&haystack[last..m.start()]);
+            replaced.push_str(&f(&m));
+
+            last = m.end();
+        }
+
+        replaced.push_str(&haystack[last..]);
+
+        replaced
+    }
+
+    /// 入力全体がパターンに一致する場合のみ、入力を `replacement` で置き換えたものを返す
+    /// `anchored_default` の指定に関わらず入力全体への一致を判定し、`$n` などは `replace_all` と同じく展開する
+    pub fn replace_if_full(&self, input: &str, replacement: &str) -> Option<String> {
+        self.assert_normalized(input);
+        if !self.full_match(input) {
+            return None;
+        }
+
+        let mut replaced = String::new();
+
+        if let Some(caps) = self.captures_in(input, 0..input.len()) {
+            caps.expand(replacement, &mut replaced);
+        }
+
+        Some(replaced)
+    }
+
+    /// パターン全体が `(X)*` の形のとき、`input` が `X` の何回の繰り返しに分解できるかを返す。
+    /// 分解の仕方が複数ある場合は最小の回数を返し、一致しない場合は `None` を返す。
+    pub fn repetition_count(&self, input: &str) -> Option<usize> {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        let (Node::Repeat(inner) | Node::Plus(inner)) = self.ast() else {
+            return None;
+        };
+
+        let inner = Nfa::from(inner.as_ref().clone());
+        let chars: Vec<char> = input.chars().collect();
+
+        // counts[i] は 先頭 i 文字を X の繰り返しに分解したときの最小の回数
+        let mut counts = vec![None; chars.len() + 1];
+        counts[0] = Some(0);
+
+        for i in 0..chars.len() {
+            let Some(count) = counts[i] else {
+                continue;
+            };
+
+            let mut states = inner.start_states(i == 0);
+
+            // i 文字目から始まる X の1回分の一致をすべて試す (空の一致は回数に数えない)
+            for (j, c) in chars.iter().enumerate().skip(i) {
+                states = inner.states_next(&states, &NfaTrans::Char(*c));
+
+                if states.is_empty() {
+                    break;
+                }
+
+                if inner.is_accepting(&states, false, j + 1 == chars.len()) {
+                    let next = count + 1;
+                    counts[j + 1] = Some(counts[j + 1].map_or(next, |c: usize| c.min(next)));
+                }
+            }
+        }
+
+        match self.ast() {
+            // X が空文字列に一致する場合、(X)+ は空文字列を X の1回分として受理する
+            Node::Plus(_) if self.full_match(input) => counts[chars.len()].map(|c| c.max(1)),
+            Node::Plus(_) => None,
+            _ => counts[chars.len()],
+        }
+    }
+
+    /// `is_full_match` と同じく全体一致を判定するが、`timeout` を過ぎた時点で `Err(Timeout)` を返して打ち切る
+    /// 現在時刻に `timeout` を足せないほど長い場合は期限なしとして扱う
+    #[cfg(feature = "std")]
+    pub fn matches_with_timeout(
+        &self,
+        pattern: &str,
+        timeout: std::time::Duration,
+    ) -> Result<bool, Timeout> {
+        #[cfg(feature = "unicode-normalization")]
+        let pattern = &*self.normalize(pattern);
+        // 時刻の取得は遷移に比べて軽くないため、一定の文字数ごとにのみ確認する
+        const CHECK_INTERVAL: usize = 64;
+
+        // DFA は1文字ごとに1回遷移するのみで時間がかかりにくいため、打ち切らない
+        if let Backend::Dfa = self.backend {
+            return Ok(self.dfa().matches(pattern));
+        }
+
+        let deadline = std::time::Instant::now().checked_add(timeout);
+
+        let mut states = self.nfa().start_states(true);
+
+        for (i, c) in pattern.chars().enumerate() {
+            if i % CHECK_INTERVAL == CHECK_INTERVAL - 1
+                && deadline.is_some_and(|d| std::time::Instant::now() >= d)
+            {
+                return Err(Timeout);
+            }
+
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+
+            if states.is_empty() {
+                return Ok(false);
+            }
+        }
+
+        Ok(self.nfa().is_accepting(&states, pattern.is_empty(), true))
+    }
+
+    /// `is_full_match` と同じく全体一致を判定するが、同時に辿る NFA の状態の数が `max_states` を超えた時点で
+    /// `Err(TooComplex)` を返して打ち切る
+    ///
+    /// 判定にかかる時間は入力の長さと同時に辿る状態の数の積に比例するため、`(a|a|a)*` のような
+    /// 曖昧なパターンが想定より遅くなることを、時間ではなく状態の数で検出できる
+    /// 状態の数には epsilon遷移の途中の状態も含まれ、パターンの長さに応じて増える点に注意する
+    pub fn matches_with_state_limit(
+        &self,
+        input: &str,
+        max_states: usize,
+    ) -> Result<bool, TooComplex> {
+        #[cfg(feature = "unicode-normalization")]
+        let input = &*self.normalize(input);
+        let check = |states: &HashSet<NfaState>| {
+            if states.len() > max_states {
+                Err(TooComplex {
+                    states: states.len(),
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        // DFA は常に1つの状態のみを辿る
+        if let Backend::Dfa = self.backend {
+            return match max_states {
+                0 => Err(TooComplex { states: 1 }),
+                _ => Ok(self.dfa().matches(input)),
+            };
+        }
+
+        let mut states = self.nfa().start_states(true);
+        check(&states)?;
+
+        for c in input.chars() {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+            check(&states)?;
+
+            if states.is_empty() {
+                return Ok(false);
+            }
+        }
+
+        Ok(self.nfa().is_accepting(&states, input.is_empty(), true))
+    }
+
+    /// 2つのパターンの AST が同じであるかを返す
+    /// 一致する言語が同じでも書き方が異なれば偽になるが、`is_subset_of` などより高速に比較できる
+    pub fn same_ast(&self, other: &Regex) -> bool {
+        self.ast() == other.ast()
+    }
+
+    /// `self` に一致するすべての文字列が `other` にも一致するかを返す
+    pub fn is_subset_of(&self, other: &Regex) -> bool {
+        self.dfa().is_subset_of(other.dfa())
+    }
+
+    /// 2つのパターンが同じ文字列の集合に一致するか (互いに `is_subset_of` であるか) を返す
+    pub fn equivalent(&self, other: &Regex) -> bool {
+        self.is_subset_of(other) && other.is_subset_of(self)
+    }
+
+    /// 一致する言語から決まるバイト列を返す。パターンの書き方に関わらず、同じ言語に一致するパターンは同じ、
+    /// 異なる言語に一致するパターンは異なるバイト列になるため、言語が同じパターンをまとめる `HashMap` のキーに使える
+    ///
+    /// 言語は `is_full_match` で一致する文字列の集合として比べる
+    /// 最小化して状態の番号を正規化した DFA を直列化したもので、形式はこのクレートの版によって変わりうる
+    pub fn minimal_dfa_signature(&self) -> Vec<u8> {
+        self.dfa().canonical().serialize()
+    }
+
+    /// `self` に一致し、`other` には一致しない文字列に一致する正規表現を返す (一致は `is_full_match` の意味で考える)
+    ///
+    /// 結果は2つの DFA の差の DFA で一致を判定するため、グループを持たず、`find` などは常に最長の一致を返す
+    /// `matcher`・`validate` なども DFA で判定する
+    /// パターンを使う機能 (`explain` や `repeated` など) は DFA から組み立て直したパターンを使い、元のパターンより長くなることがある
+    /// `.` で表せない「特定の文字以外の任意の1文字」が必要になる場合 (`.` と `a` の差など) は、パターンを使う機能は panic する
+    /// ビルダーの設定は `self` のものを引き継ぐ
+    pub fn difference(&self, other: &Regex) -> Regex {
+        Self::with_dfa(self.dfa().difference(other.dfa()), &self.builder)
+    }
+
+    /// パターンに一致する文字列が必ず始まる文字列を返す
+    pub fn required_prefix(&self) -> String {
+        self.ast().literal_prefix().0
+    }
+
+    /// 2つのパターンの `required_prefix` に共通する最長の接頭辞を返す
+    pub fn common_prefix_with(&self, other: &Regex) -> String {
+        optimizer::common_prefix(&self.required_prefix(), &other.required_prefix())
+    }
+
+    /// パターンを `n` 回連接した (`(?:pattern){n}` に相当する) 正規表現を返す
+    /// `n` が 0 の場合は空文字列にのみ一致し、ビルダーでの指定は引き継ぐ
+    pub fn repeated(&self, n: usize) -> Regex {
+        let ast = match n {
+            0 => Node::Empty,
+            1 => self.ast().clone(),
+            _ => Node::Concat(vec![self.ast().clone(); n]),
+        };
+
+        Self::from_node(ast, &self.builder)
+    }
+
+    /// パターンの前後に `.*` を付けた (`.*(?:pattern).*` に相当する) 正規表現を返す
+    /// `is_full_match` が入力のどこかにパターンに一致する部分があるかを判定するようになる
+    /// `find` などの一致の範囲も前後の `.*` の分だけ広がるため、位置が必要な場合は元の正規表現の `find` を使う
+    pub fn to_unanchored(&self) -> Regex {
+        let any = Node::Repeat(Box::new(Node::Any));
+
+        Self::from_node(
+            Node::Concat(vec![any.clone(), self.ast().clone(), any]),
+            &self.builder,
+        )
+    }
+
+    /// パターンを `\A` と `\z` で囲んだ (`\A(?:pattern)\z` に相当する) 正規表現を返す
+    /// `is_full_match` での判定は元の正規表現と変わらず、`is_match` や `find` も入力全体への一致のみを判定するようになる
+    pub fn to_anchored(&self) -> Regex {
+        Self::from_node(
+            Node::Concat(vec![
+                Node::Look(Look::StartText),
+                self.ast().clone(),
+                Node::Look(Look::EndText),
+            ]),
+            &self.builder,
+        )
+    }
+
+    /// パターンに一致する文字列が有限個であるかを返す
+    /// `.` はすべての文字に一致するが、文字の種類は有限であるため `a.` も有限として扱う
+    pub fn is_finite_language(&self) -> bool {
+        self.dfa().is_finite()
+    }
+
+    /// パターンに一致するすべての文字列を辞書順に返す
+    /// 一致する文字列が無限個の場合と、`.` により一致する文字が多すぎる場合は None
+    pub fn enumerate(&self) -> Option<Vec<String>> {
+        self.dfa().enumerate()
+    }
+
+    /// パターンに一致する最も短い文字列の例を返す。一致する文字列が無い場合は None
+    pub fn shortest_example(&self) -> Option<String> {
+        self.dfa().shortest_example()
+    }
+
+    /// 長さ (文字数) が `max_len` 以下で、読み終えた時点で NFA の状態集合が空にならない文字列を、短い順・辞書順に返す
+    /// 受理するかどうかは問わないため、「次に入力できる文字」の候補を示すのに使える。空文字列は含まない
+    /// `.` はすべての文字に一致するため列挙せず、`.` でのみ続けられる文字列は含まない
+    pub fn live_prefixes(&self, max_len: usize) -> Vec<String> {
+        let mut prefixes = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back((String::new(), self.nfa().start_states(true)));
+
+        while let Some((prefix, states)) = queue.pop_front() {
+            if prefix.chars().count() == max_len {
+                continue;
+            }
+
+            let chars: BTreeSet<char> = states
+                .iter()
+                .filter_map(|s| self.nfa().states().get(s))
+                .flat_map(|transs| transs.keys())
+                .filter_map(|t| match t {
+                    NfaTrans::Char(c) => Some(*c),
+                    NfaTrans::Epsilon | NfaTrans::Any | NfaTrans::Look(_) => None,
+                })
+                .collect();
+
+            for c in chars {
+                let nexts = self.nfa().states_next(&states, &NfaTrans::Char(c));
+
+                if nexts.is_empty() {
+                    continue;
+                }
+
+                let mut next = prefix.clone();
+                next.push(c);
+
+                prefixes.push(next.clone());
+                queue.push_back((next, nexts));
+            }
+        }
+
+        prefixes
+    }
+
+    /// 入力を少しずつ与えて一致を調べる `Matcher` を返す
+    /// 1文字ずつ与える入力は正規化できないため、ビルダーで `normalize_input` を指定した場合は panic する
+    /// (入力全体を一度に与える `partial_match` は正規化して判定する)
+    pub fn matcher(&self) -> Matcher<'_> {
+        #[cfg(feature = "unicode-normalization")]
+        assert!(
+            self.builder.normalization().is_none(),
+            "`matcher` cannot normalize its input, use `partial_match` with `normalize_input`"
+        );
+
+        Matcher::new(self)
+    }
+
+    /// NFA の開始状態から到達できる状態を、幅優先でそれぞれの遷移と共に列挙する
+    #[cfg(feature = "internals")]
+    pub fn iter_states_bfs(&self) -> NfaBfs<'_> {
+        NfaBfs::new(self.nfa())
+    }
+
+    /// 入力全体に一致するかを、DFA のうち入力が通る状態のみを作って判定する
+    /// 一致するかと、作った DFA の状態の数を返す (`Dfa::from` はすべての状態を作る)
+    #[cfg(feature = "internals")]
+    pub fn determinize_partial(&self, input: &str) -> (bool, usize) {
+        self.nfa().determinize_partial(input)
+    }
+
+    /// 入力の先頭 (0) と各文字を読んだ後のバイトオフセットごとに、その時点の NFA の状態集合を番号の順に返す
+    /// 状態集合が空になった時点で終える
+    #[cfg(feature = "internals")]
+    pub fn trace(&self, input: &str) -> Vec<(usize, Vec<NfaState>)> {
+        let sorted = |states: &HashSet<NfaState>| {
+            let mut states: Vec<NfaState> = states.iter().copied().collect();
+            states.sort();
+
+            states
+        };
+
+        let mut states = self.nfa().start_states(true);
+        let mut trace = vec![(0, sorted(&states))];
+
+        for (i, c) in input.char_indices() {
+            states = self.nfa().states_next(&states, &NfaTrans::Char(c));
+            trace.push((i + c.len_utf8(), sorted(&states)));
+
+            if states.is_empty() {
+                break;
+            }
+        }
+
+        trace
+    }
+
+    // DFA から作った場合は最初に呼んだときに DFA からパターンを求め、パターンで表せない DFA の場合は panic する
+    pub(crate) fn ast(&self) -> &Node {
+        self.ast
+            .get_or_init(|| self.dfa().to_node())
+            .as_ref()
+            .expect("the DFA of this regex cannot be written as a pattern")
+    }
+
+    pub(crate) fn options(&self) -> &RegexBuilder {
+        &self.builder
+    }
+
+    /// パターンから部分集合構成法で作った DFA を返す。最初に呼んだときに作り、以降は同じものを返す
+    /// DFA はパターン全体への一致 (`is_full_match`) を判定する
+    #[cfg(feature = "internals")]
+    pub fn as_dfa(&self) -> &Dfa {
+        self.dfa()
+    }
+
+    /// DFA が受理する文字列全体に一致する正規表現を返す
+    ///
+    /// 一致の判定や `find`・`matcher` などは渡した DFA で行い、`as_dfa` は渡した DFA をそのまま返す
+    /// DFA はグループと分岐の優先順位を持たないため、`captures` は一致全体のみを、`find` などは常に最長の一致を返す
+    /// パターンを使う機能 (`explain` や `repeated` など) は DFA から状態除去法で求めたパターンを使い、
+    /// 「アルファベット以外の文字」の遷移が `.` として表せない DFA の場合は panic する
+    #[cfg(feature = "internals")]
+    pub fn from_dfa(dfa: Dfa) -> Regex {
+        Self::with_dfa(dfa, &RegexBuilder::new())
+    }
+
+    /// `Dfa::serialize` で書き出したバイト列から DFA を読み込み、`from_dfa` と同じくその DFA で一致を判定する正規表現を返す
+    ///
+    /// バイト列が不正な場合は `Dfa::deserialize` のエラーを返す
+    #[cfg(feature = "internals")]
+    pub fn from_dfa_bytes(bytes: &[u8]) -> Result<Regex, crate::dfa::DecodeError> {
+        Ok(Self::from_dfa(Dfa::deserialize(bytes)?))
+    }
+
+    pub(crate) fn dfa(&self) -> &Dfa {
+        self.dfa.get_or_init(|| Dfa::from(self.nfa()))
+    }
+
+    // `ast` と同じく、DFA から作った場合はパターンで表せないと panic する
+    pub(crate) fn nfa(&self) -> &Nfa {
+        self.nfa.get_or_init(|| Nfa::from(self.ast().clone()))
+    }
+
+    fn program(&self) -> &Program {
+        self.program
+            .get_or_init(|| Program::new(self.ast(), self.builder.is_captures()))
+    }
+
+    pub(crate) fn live_states(&self) -> &HashSet<NfaState> {
+        self.live_states.get_or_init(|| self.nfa().live_states())
+    }
+
+    pub(crate) fn live_dfa_states(&self) -> &HashSet<DfaState> {
+        self.live_dfa_states
+            .get_or_init(|| self.dfa().useful_states())
+    }
+
+    // 一致を DFA で判定する場合はその DFA を返す
+    pub(crate) fn backend_dfa(&self) -> Option<&Dfa> {
+        match self.backend {
+            Backend::Nfa => None,
+            Backend::Dfa => Some(self.dfa()),
+        }
+    }
+
+    pub(crate) fn reversed_nfa(&self) -> &Nfa {
+        self.reversed_nfa
+            .get_or_init(|| Nfa::from(self.ast().reversed()))
+    }
+}
+
+pub struct FindIter<'r, 'h> {
+    regex: &'r Regex,
+    haystack: &'h str,
+    at: Option<usize>,
+}
+
+impl<'h> Iterator for FindIter<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.regex.find_at(self.haystack, self.at?)?;
+
+        self.at = if m.is_empty() {
+            // 空の一致の場合は無限ループを避けるため次の文字まで進める
+            self.haystack[m.end()..]
+                .chars()
+                .next()
+                .map(|c| m.end() + c.len_utf8())
+        } else {
+            Some(m.end())
+        };
+
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        cell::{Cell, RefCell},
+    };
+
+    use crate::{
+        builder::{Anchored, MatchKind},
+        lexer::TokenKind,
+        regex::{Regex, RegexParseError},
+    };
+
+    thread_local! {
+        // 入力全体への一致を NFA のシミュレーションで判定した回数
+        pub(super) static FULL_SIMULATIONS: Cell<usize> = const { Cell::new(0) };
+        // longest_match_at で一致を探し始めた位置
+        pub(super) static MATCH_STARTS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[test]
+    fn regex_works() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+
+        assert!(regex.is_full_match("a"));
+        assert!(regex.is_full_match("ab"));
+        assert!(regex.is_full_match("ac"));
+        assert!(!regex.is_full_match("b"));
+        assert!(!regex.is_full_match("bcb"));
+        assert!(regex.is_full_match("acbbc"));
+    }
+
+    #[test]
+    fn regex_works2() {
+        let regex = Regex::new("a*b*").unwrap();
+
+        assert!(regex.is_full_match("")); // 空文字可
+        assert!(regex.is_full_match("a"));
+        assert!(regex.is_full_match("aa"));
+        assert!(regex.is_full_match("b"));
+        assert!(regex.is_full_match("bb"));
+        assert!(regex.is_full_match("aaabbb"));
+        assert!(regex.is_full_match("aaa"));
+        assert!(regex.is_full_match("bbb"));
+        assert!(!regex.is_full_match("abba")); // a* の後は b* のみ
+        assert!(!regex.is_full_match("ba")); // b の後に a は不可
+    }
+
+    #[test]
+    fn regex_works3() {
+        let regex = Regex::new("(ab)*|c").unwrap();
+
+        assert!(regex.is_full_match("")); // (ab)* → 空文字 ok
+        assert!(regex.is_full_match("ab"));
+        assert!(regex.is_full_match("abab"));
+        assert!(regex.is_full_match("ababab"));
+        assert!(regex.is_full_match("c"));
+        assert!(!regex.is_full_match("a")); // ab の途中
+        assert!(!regex.is_full_match("b"));
+        assert!(!regex.is_full_match("abc")); // 全体一致ではない
+        assert!(!regex.is_full_match("cab")); // 全体一致ではない
+        assert!(!regex.is_full_match("cc")); // c 1文字のみ
+    }
+
+    #[test]
+    fn regex_works4() {
+        let regex = Regex::new("a(bc)*d").unwrap();
+
+        assert!(regex.is_full_match("ad")); // bc が0回
+        assert!(regex.is_full_match("abcd")); // bc 1回
+        assert!(regex.is_full_match("abcbcd")); // bc 2回
+        assert!(regex.is_full_match("abcbcbcd")); // bc 3回
+        assert!(!regex.is_full_match("a")); // d が無い
+        assert!(!regex.is_full_match("d")); // 先頭が a でない
+        assert!(!regex.is_full_match("abc")); // 末尾が d でない
+        assert!(!regex.is_full_match("abcbd")); // bc の途中壊れ
+        assert!(!regex.is_full_match("aabcbcd")); // 最初に a が2つ
+        assert!(!regex.is_full_match("abcbcccd")); // bc の途中に c が余分
+    }
+
+    #[test]
+    fn repetition_count_works() {
+        let regex = Regex::new("(ab)*").unwrap();
+
+        assert_eq!(regex.repetition_count("ababab"), Some(3));
+        assert_eq!(regex.repetition_count("ab"), Some(1));
+        assert_eq!(regex.repetition_count(""), Some(0));
+        assert_eq!(regex.repetition_count("aba"), None); // ab の途中
+
+        let regex = Regex::new("(a|(aa))*").unwrap();
+        assert_eq!(regex.repetition_count("aaaa"), Some(2)); // 最小の分解 aa + aa
+
+        let regex = Regex::new("a(b)*").unwrap();
+        assert_eq!(regex.repetition_count("abb"), None); // トップレベルが繰り返しでない
+
+        let regex = Regex::new("(ab)+").unwrap();
+        assert_eq!(regex.repetition_count("abab"), Some(2));
+        assert_eq!(regex.repetition_count(""), None);
+
+        let regex = Regex::new("(a?)+").unwrap();
+        assert_eq!(regex.repetition_count(""), Some(1));
+    }
+
+    #[test]
+    fn parse_error_into_boxed_error() {
+        fn compile(re: &str) -> Result<Regex, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Regex::new(re)?)
+        }
+
+        assert!(compile("a(b|c)*").unwrap().is_full_match("abc"));
+
+        let err = compile("a(b").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to parse regex, unexpected `EOF` found"
+        );
+    }
+
+    #[test]
+    fn regex_subset() {
+        let ab = Regex::new("ab").unwrap();
+        let a_bc = Regex::new("a(b|c)*").unwrap();
+
+        assert!(ab.is_subset_of(&a_bc));
+        assert!(!a_bc.is_subset_of(&ab));
+
+        let a_star = Regex::new("a*").unwrap();
+        let a_plus = Regex::new("aa*").unwrap();
+
+        assert!(!a_star.is_subset_of(&a_plus)); // 空文字は aa* に一致しない
+        assert!(a_plus.is_subset_of(&a_star));
+    }
+
+    #[test]
+    fn empty_branch_is_optional() {
+        let empty_branch = Regex::new("(a|)").unwrap();
+        let optional = Regex::new("a?").unwrap();
+
+        assert!(empty_branch.equivalent(&optional));
+        assert_eq!(
+            empty_branch.minimal_dfa_signature(),
+            optional.minimal_dfa_signature()
+        );
+
+        // 空の分岐から受理状態への epsilon遷移がある
+        let nfa = empty_branch.nfa();
+        assert!(nfa.is_accepting(&nfa.start_states(true), true, true));
+        assert!(empty_branch.is_full_match(""));
+        assert!(empty_branch.is_full_match("a"));
+        assert!(!empty_branch.is_full_match("aa"));
+
+        assert!(Regex::new("|a").unwrap().equivalent(&optional));
+        assert!(
+            Regex::new("x(a|)y")
+                .unwrap()
+                .equivalent(&Regex::new("xa?y").unwrap())
+        );
+        assert!(!empty_branch.equivalent(&Regex::new("a").unwrap()));
+    }
+
+    #[cfg(feature = "internals")]
+    #[test]
+    fn as_dfa_works() {
+        let regex = Regex::new(r"a(b|c)*|\A.x").unwrap();
+        let dfa = regex.as_dfa();
+
+        for s in ["a", "abcb", "zx", "ax", "", "abd", "x"] {
+            assert_eq!(dfa.matches(s), regex.is_full_match(s), "{s:?}");
+        }
+        // 2回目以降は同じ DFA を返す
+        assert!(std::ptr::eq(dfa, regex.as_dfa()));
+        assert!(dfa.minimize().matches("ax"));
+    }
+
+    #[cfg(feature = "internals")]
+    #[test]
+    fn from_dfa_works() {
+        use crate::dfa::Dfa;
+
+        let dfa = Regex::new("a(b|c)*d").unwrap().as_dfa().minimize();
+        let bytes = dfa.serialize();
+        let regex = Regex::from_dfa(Dfa::deserialize(&bytes).unwrap());
+
+        for s in ["ad", "abcbd", "a", "abd d", "", "xad"] {
+            assert_eq!(regex.is_full_match(s), dfa.matches(s), "{s:?}");
+        }
+        assert_eq!(regex.find("xxabdx").map(|m| m.range()), Some(2..5));
+        assert_eq!(regex.as_dfa().serialize(), bytes);
+
+        // `.` を含む DFA
+        let dfa = Regex::new("a.*").unwrap().as_dfa().minimize();
+        let regex = Regex::from_dfa(dfa);
+        assert!(regex.is_full_match("axyz"));
+        assert!(!regex.is_full_match("xa"));
+
+        // パターンで表せない DFA も受け付け、一致はその DFA で判定する
+        let dfa = Regex::new("a|.b")
+            .unwrap()
+            .as_dfa()
+            .difference(Regex::new("ab").unwrap().as_dfa());
+        let regex = Regex::from_dfa(dfa);
+        for s in ["a", "xb", "bb", "ab", "", "b"] {
+            assert_eq!(regex.is_full_match(s), regex.as_dfa().matches(s), "{s:?}");
+        }
+        assert!(regex.is_full_match("xb") && !regex.is_full_match("ab"));
+        assert_eq!(regex.find("abxb").map(|m| m.range()), Some(0..1));
+        assert_eq!(regex.captures("zzxb").map(|c| c.len()), Some(1));
+    }
+
+    #[cfg(feature = "internals")]
+    #[test]
+    fn from_dfa_bytes_round_trip() {
+        use crate::dfa::DecodeError;
+
+        let original = Regex::new("a(b|c)*d|x.").unwrap();
+        let bytes = original.as_dfa().serialize();
+        let regex = Regex::from_dfa_bytes(&bytes).unwrap();
+
+        for s in ["ad", "abcbd", "xy", "x", "abd d", "", "xad"] {
+            assert_eq!(regex.is_full_match(s), original.is_full_match(s), "{s:?}");
+        }
+        assert_eq!(regex.find("zzabdx").map(|m| m.range()), Some(2..5));
+        assert_eq!(regex.as_dfa().serialize(), bytes);
+
+        assert!(matches!(
+            Regex::from_dfa_bytes(b"XXXX"),
+            Err(DecodeError::InvalidMagic)
+        ));
+        assert!(matches!(
+            Regex::from_dfa_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn matches_with_timeout_works() {
+        use std::time::Duration;
+
+        use crate::regex::Timeout;
+
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let input = format!("a{}", "bc".repeat(1000));
+
+        assert_eq!(
+            regex.matches_with_timeout(&input, Duration::from_secs(60)),
+            Ok(true)
+        );
+        assert_eq!(
+            regex.matches_with_timeout("abd", Duration::from_secs(60)),
+            Ok(false)
+        );
+        assert_eq!(
+            regex.matches_with_timeout(&input, Duration::ZERO),
+            Err(Timeout)
+        );
+
+        // 期限が表せないほど長い場合も桁あふれせず、打ち切らない
+        assert_eq!(regex.matches_with_timeout(&input, Duration::MAX), Ok(true));
+    }
+
+    #[test]
+    fn matches_with_state_limit_works() {
+        use crate::regex::TooComplex;
+
+        let input = "a".repeat(1000);
+
+        let regex = Regex::new("(a|a|a)*").unwrap();
+        assert!(matches!(
+            regex.matches_with_state_limit(&input, 8),
+            Err(TooComplex { states }) if states > 8
+        ));
+        assert_eq!(regex.matches_with_state_limit(&input, 1000), Ok(true));
+
+        let regex = Regex::new("a*").unwrap();
+        assert_eq!(regex.matches_with_state_limit(&input, 8), Ok(true));
+        assert_eq!(regex.matches_with_state_limit("ab", 8), Ok(false));
+    }
+
+    #[test]
+    fn is_full_match_and_matches_agree() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+
+        for s in ["a", "abc", "", "xa", "ab c"] {
+            #[allow(deprecated)]
+            let matched = regex.matches(s);
+
+            assert_eq!(regex.is_full_match(s), matched);
+        }
+
+        // is_full_match は anchored_default に関わらず全体への一致
+        let regex = Regex::builder()
+            .anchored_default(Anchored::No)
+            .build_from("bc")
+            .unwrap();
+        assert!(!regex.is_full_match("abcd"));
+        assert!(regex.is_full_match("bc"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn anchored_default() {
+        let regex = Regex::new("bc").unwrap();
+        assert!(!regex.matches("abcd"));
+        assert!(regex.matches("bc"));
+        assert!(regex.is_match("abcd"));
+
+        let regex = Regex::builder()
+            .anchored_default(Anchored::No)
+            .build_from("bc")
+            .unwrap();
+        assert!(regex.matches("abcd"));
+        assert!(regex.is_match("abcd"));
+        assert!(!regex.matches("acbd"));
+
+        let regex = Regex::builder()
+            .anchored_default(Anchored::Yes)
+            .build_from("bc")
+            .unwrap();
+        assert!(!regex.matches("abcd"));
+        assert!(!regex.is_match("abcd"));
+        assert!(regex.is_match("bc"));
+
+        // パターン中のアンカーは指定に関わらず有効
+        let regex = Regex::builder()
+            .anchored_default(Anchored::No)
+            .build_from(r"\Abc\z")
+            .unwrap();
+        assert!(!regex.matches("abcd"));
+        assert!(regex.matches("bc"));
+    }
+
+    #[test]
+    fn debug_is_concise() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let debug = format!("{regex:?}");
+
+        assert_eq!(debug, r#"Regex { pattern: "a(b|c)*", nfa_states: 11 }"#);
+        assert!(!debug.contains("NfaState"));
+    }
+
+    #[test]
+    fn find_works() {
+        let regex = Regex::new("b(c)*").unwrap();
+
+        assert_eq!(regex.find("aabcca").map(|m| m.range()), Some(2..5)); // 最長一致
+        assert_eq!(regex.find("aaa").map(|m| m.range()), None);
+
+        let ranges: Vec<_> = regex.find_iter("bcxbxbcc").map(|m| m.range()).collect();
+        assert_eq!(ranges, vec![0..2, 3..4, 5..8]);
+
+        let regex = Regex::new("a*").unwrap();
+        let ranges: Vec<_> = regex.find_iter("baa").map(|m| m.range()).collect();
+        assert_eq!(ranges, vec![0..0, 1..3, 3..3]); // 空の一致の後は1文字進む
+    }
+
+    #[test]
+    fn len_prescreen_skips_simulation() {
+        let regex = Regex::new("(0|1|2|3|4|5|6|7|8|9){4}").unwrap();
+        assert_eq!(regex.match_len_bounds(), (4, Some(4)));
+
+        let simulations = || FULL_SIMULATIONS.with(Cell::get);
+        let before = simulations();
+
+        assert!(!regex.is_full_match("12345"));
+        assert!(!regex.is_full_match("123"));
+        assert!(!regex.is_full_match("ああああ1")); // 5文字 (13バイト)
+        assert_eq!(simulations(), before);
+
+        assert!(regex.is_full_match("1234"));
+        assert!(!regex.is_full_match("12あ4")); // 4文字 (6バイト) のため判定する
+        assert_eq!(simulations(), before + 2);
+
+        let regex = Regex::new("あ.+").unwrap();
+        assert!(regex.is_full_match("ああ"));
+        assert!(!regex.is_full_match("あ"));
+    }
+
+    #[test]
+    fn first_chars_skip_positions() {
+        let starts = |regex: &Regex, haystack: &str| {
+            MATCH_STARTS.with(|starts| starts.borrow_mut().clear());
+            let found = regex.find(haystack).map(|m| m.range());
+
+            (found, MATCH_STARTS.with(|starts| starts.take()))
+        };
+
+        let regex = Regex::new("b+").unwrap();
+        assert_eq!(starts(&regex, "aaaab"), (Some(4..5), vec![4]));
+        assert_eq!(starts(&regex, "aaaa"), (None, vec![]));
+        assert!(regex.is_match("abb"));
+        assert!(!regex.is_full_match("abb"));
+
+        let regex = Regex::new(r"(x|\Ay)z").unwrap();
+        assert_eq!(starts(&regex, "ayxz"), (Some(2..4), vec![1, 2]));
+        assert_eq!(starts(&regex, "yz"), (Some(0..2), vec![0]));
+
+        // 空文字列や `.` で始まりうる場合はすべての位置から探す
+        let regex = Regex::new("b*").unwrap();
+        assert_eq!(starts(&regex, "ab"), (Some(0..0), vec![0]));
+        let regex = Regex::new(".b").unwrap();
+        assert_eq!(starts(&regex, "aab"), (Some(1..3), vec![0, 1]));
+    }
+
+    #[test]
+    fn matches_ci_ascii_works() {
+        let regex = Regex::new("hello(.|!)").unwrap();
+
+        assert!(regex.matches_ci_ascii("HELLO!"));
+        assert!(regex.matches_ci_ascii("hElLo?"));
+        assert!(!regex.is_full_match("HELLO!"));
+        assert!(!regex.matches_ci_ascii("HELL!"));
+
+        // ASCII 以外の文字は区別する
+        let regex = Regex::new("caf(é|x)").unwrap();
+        assert!(regex.matches_ci_ascii("CAFé"));
+        assert!(regex.matches_ci_ascii("CAFX"));
+        assert!(!regex.matches_ci_ascii("CAFÉ"));
+    }
+
+    #[test]
+    fn leftmost_first() {
+        let first = |re: &str| {
+            Regex::builder()
+                .match_kind(MatchKind::LeftmostFirst)
+                .captures(true)
+                .build_from(re)
+                .unwrap()
+        };
+
+        // 前に書いた分岐が優先される
+        assert_eq!(first("a|ab").find("ab").map(|m| m.range()), Some(0..1));
+        assert_eq!(first("ab|a").find("ab").map(|m| m.range()), Some(0..2));
+        assert_eq!(
+            Regex::new("a|ab").unwrap().find("ab").map(|m| m.range()),
+            Some(0..2)
+        );
+
+        assert_eq!(
+            first("a|ab")
+                .find_iter("abab")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..1, 2..3]
+        );
+        assert_eq!(
+            first("x(a|ab)(c|bcd)").find("xabcd").map(|m| m.range()),
+            Some(0..5)
+        );
+
+        let caps = first("(a|ab)(b*)").captures("abb").unwrap();
+        assert_eq!(caps.get(0).map(|m| m.range()), Some(0..3));
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(0..1));
+        assert_eq!(caps.get(2).map(|m| m.range()), Some(1..3));
+
+        // 一致するかどうかは変わらない
+        assert!(first("a|ab").is_full_match("ab"));
+    }
+
+    #[test]
+    fn find_at_works() {
+        let regex = Regex::new("a").unwrap();
+
+        assert_eq!(regex.find_at("aXaY", 0).map(|m| m.range()), Some(0..1));
+        assert_eq!(regex.find_at("aXaY", 1).map(|m| m.range()), Some(2..3));
+        assert_eq!(regex.find_at("aXaY", 3).map(|m| m.range()), None);
+        assert_eq!(regex.find_at("aXaY", 4).map(|m| m.range()), None);
+
+        // `\A` は探索の開始位置ではなく文字列の先頭
+        let regex = Regex::new(r"\Aa").unwrap();
+        assert_eq!(regex.find_at("aXaY", 1).map(|m| m.range()), None);
+
+        let regex = Regex::new("あ*").unwrap();
+        assert_eq!(regex.find_at("xああ", 1).map(|m| m.range()), Some(1..7));
+    }
+
+    #[test]
+    fn find_str_works() {
+        let regex = Regex::new("b(c)*").unwrap();
+
+        assert_eq!(regex.find_str("aabcc"), Some("bcc"));
+        assert_eq!(regex.find_str("あbcい"), Some("bc"));
+        assert_eq!(regex.find_str("aa"), None);
+        assert_eq!(Regex::new("x*").unwrap().find_str("abc"), Some(""));
+    }
+
+    #[test]
+    fn find_returns_match() {
+        let m = Regex::new("bc").unwrap().find("abcd").unwrap();
+
+        assert_eq!(m.start(), 1);
+        assert_eq!(m.end(), 3);
+        assert_eq!(m.range(), 1..3);
+        assert_eq!(m.as_str(), "bc");
+        assert_eq!(m.len(), 2);
+
+        // 範囲はバイトオフセット
+        let m = Regex::new("い+").unwrap().find("あいい").unwrap();
+        assert_eq!((m.start(), m.end(), m.as_str()), (3, 9, "いい"));
+
+        let m = Regex::new("x*").unwrap().find("abc").unwrap();
+        assert!(m.is_empty());
+
+        let regex = Regex::builder().captures(true).build_from("a(b)").unwrap();
+        let caps = regex.captures("xab").unwrap();
+        assert_eq!(caps.get(1).map(|m| m.as_str()), Some("b"));
+        assert_eq!(caps.get(1).map(|m| m.start()), Some(2));
+    }
+
+    #[test]
+    fn find_last_works() {
+        let regex = Regex::new("aa*").unwrap();
+
+        assert_eq!(regex.find_last("aa b aaa").map(|m| m.range()), Some(5..8));
+        assert_eq!(regex.find_last("bbb").map(|m| m.range()), None);
+
+        // 重ならない一致の最後であり、右端から探した一致 (bab の 2..3) ではない
+        let regex = Regex::new("bab|b").unwrap();
+        assert_eq!(regex.find_last("bab").map(|m| m.range()), Some(0..3));
+    }
+
+    #[test]
+    fn match_ranges_iter_steps_by_char() {
+        let regex = Regex::new("a*").unwrap();
+
+        assert_eq!(
+            regex
+                .match_ranges_iter("é")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..0, 2..2]
+        );
+        assert_eq!(
+            regex
+                .match_ranges_iter("aéa")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..1, 1..1, 3..4, 4..4]
+        );
+
+        let haystack = "あaいaa";
+        for range in regex.match_ranges_iter(haystack).map(|m| m.range()) {
+            assert!(haystack.is_char_boundary(range.start));
+            assert!(haystack.is_char_boundary(range.end));
+        }
+    }
+
+    #[test]
+    fn find_iter_overlapping_works() {
+        let regex = Regex::new("aba").unwrap();
+
+        assert_eq!(
+            regex
+                .find_iter_overlapping("ababa")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..3, 2..5]
+        );
+        assert_eq!(
+            regex
+                .find_iter("ababa")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..3]
+        );
+
+        let regex = Regex::new("a*").unwrap();
+        assert_eq!(
+            regex
+                .find_iter_overlapping("aab")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..2, 1..2, 2..2, 3..3]
+        );
+    }
+
+    #[test]
+    fn quote_if_needed_works() {
+        assert!(matches!(
+            Regex::quote_if_needed("abc"),
+            Cow::Borrowed("abc")
+        ));
+        assert_eq!(Regex::quote_if_needed("a*b"), r"a\*b");
+        assert!(matches!(Regex::quote_if_needed("a*b"), Cow::Owned(_)));
+
+        let text = r"(a|b)+\.{2}";
+        let regex = Regex::new(&Regex::escape(text)).unwrap();
+        assert!(regex.is_full_match(text));
+        assert!(!regex.is_full_match("a"));
+    }
+
+    #[test]
+    fn captures_works() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(ab*)-((c)|d)")
+            .unwrap();
+
+        let caps = regex.captures("xabb-cy").unwrap();
+        assert_eq!(caps.len(), 4);
+        assert_eq!(caps.get(0).map(|m| m.range()), Some(1..6));
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(1..4));
+        assert_eq!(caps.get(2).map(|m| m.range()), Some(5..6));
+        assert_eq!(caps.get(3).map(|m| m.range()), Some(5..6));
+
+        let caps = regex.captures("a-d").unwrap();
+        assert_eq!(caps.get(2).map(|m| m.range()), Some(2..3));
+        assert_eq!(caps.get(3).map(|m| m.range()), None); // 一致に関与していない
+        assert_eq!(caps.get(4).map(|m| m.range()), None); // 存在しない
+
+        assert!(regex.captures("ab-").is_none());
+    }
+
+    #[test]
+    fn capture_count_works() {
+        let count = |re: &str| {
+            Regex::builder()
+                .captures(true)
+                .build_from(re)
+                .unwrap()
+                .capture_count()
+        };
+
+        assert_eq!(count("(a)(b(c))"), 3);
+        assert_eq!(count("(?:a)(b)|c"), 1);
+        assert_eq!(count("(a){3}"), 1); // 回数指定で展開してもグループは1つ
+        assert_eq!(count("abc"), 0);
+        assert_eq!(Regex::new("(a)(b(c))").unwrap().capture_count(), 0);
+
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(a)(b(c))")
+            .unwrap();
+        let mut slots = Vec::new();
+        assert!(regex.captures_read("abc", &mut slots));
+        assert_eq!(slots.len(), regex.capture_count() + 1);
+    }
+
+    #[test]
+    fn capture_names_works() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(a)-(b)")
+            .unwrap();
+        assert_eq!(regex.capture_names(), vec![None, None, None]);
+
+        let digits = "(?:0|1|2|3|4|5|6|7|8|9)+";
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from(&format!("(?<year>{digits})-(?<month>{digits})"))
+            .unwrap();
+        assert_eq!(
+            regex.capture_names(),
+            vec![None, Some("year".to_string()), Some("month".to_string())]
+        );
+        let caps = regex.captures("2024-10").unwrap();
+        assert_eq!(caps.get(1).map(|m| m.as_str()), Some("2024"));
+        assert_eq!(caps.get(2).map(|m| m.as_str()), Some("10"));
+
+        assert_eq!(Regex::new("(a)-(b)").unwrap().capture_names(), vec![None]);
+
+        let err = Regex::new("(?<n>a)(?<n>b)").unwrap_err();
+        assert!(matches!(&err, RegexParseError::DuplicateGroupName(n) if n == "n"));
+        assert_eq!(
+            err.to_string(),
+            "failed to parse regex, the group name `n` is used more than once"
+        );
+        assert!(matches!(
+            Regex::new("(?<>a)"),
+            Err(RegexParseError::EmptyGroupName)
+        ));
+        // 回数指定で展開したグループは同じグループとして扱う
+        assert!(Regex::new("(?<n>a){2}").is_ok());
+    }
+
+    #[test]
+    fn repeated_group_captures_last_iteration() {
+        let captures = Regex::builder().captures(true);
+        let group1 = |re: &str, haystack: &str| {
+            captures
+                .build_from(re)
+                .unwrap()
+                .captures(haystack)
+                .and_then(|caps| caps.get(1).map(|m| m.range()))
+        };
+
+        assert_eq!(group1("(a)*", "aaa"), Some(2..3));
+        assert_eq!(group1("(a)+", "aaa"), Some(2..3));
+        assert_eq!(group1("(a|b)*c", "abac"), Some(2..3));
+        assert_eq!(group1("x(a)*", "x"), None); // 一度も繰り返さない場合は一致に関与していない
+
+        // 回数指定の展開でも最後の繰り返しの範囲になる
+        assert_eq!(group1("(a){3}", "aaa"), Some(2..3));
+        assert_eq!(group1("(a){1,3}", "aa"), Some(1..2));
+        assert_eq!(group1("(a(b)?){2,}", "aabab"), Some(3..5));
+        assert_eq!(group1("(a){0,2}b", "ab"), Some(0..1));
+        assert_eq!(group1("(a){0,2}b", "b"), None);
+
+        let mut slots = Vec::new();
+        assert!(
+            captures
+                .build_from("(a)*")
+                .unwrap()
+                .captures_read("aaa", &mut slots)
+        );
+        assert_eq!(slots, vec![Some(0..3), Some(2..3)]);
+    }
+
+    #[test]
+    fn captures_read_reuses_slots() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(a+)(b)?")
+            .unwrap();
+        let mut slots = Vec::new();
+
+        assert!(regex.captures_read("xaab", &mut slots));
+        assert_eq!(slots, vec![Some(1..4), Some(1..3), Some(3..4)]);
+
+        assert!(regex.captures_read("a", &mut slots));
+        assert_eq!(slots, vec![Some(0..1), Some(0..1), None]);
+
+        assert!(!regex.captures_read("xyz", &mut slots));
+        assert!(slots.is_empty());
+
+        assert!(regex.captures_read("bab", &mut slots));
+        assert_eq!(slots, vec![Some(1..3), Some(1..2), Some(2..3)]);
+    }
+
+    #[test]
+    fn captures_text_works() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(a+)b|(c)")
+            .unwrap();
+
+        let caps = regex.captures("xaaab").unwrap();
+        assert_eq!(caps.text(0), Some("aaab"));
+        assert_eq!(caps.text(1), Some("aaa"));
+        assert_eq!(caps.text(2), None); // 一致に関与していない
+        assert_eq!(caps.text(3), None); // 存在しない
+    }
+
+    #[test]
+    fn matches_with_captures_vm_works() {
+        let regex = Regex::builder().captures(true).build_from("(ab)+").unwrap();
+
+        for s in ["ab", "abab", "", "aba", "xab", "ababab"] {
+            assert_eq!(
+                regex.matches_with_captures_vm(s).is_some(),
+                regex.is_full_match(s)
+            );
+        }
+
+        let caps = regex.matches_with_captures_vm("ababab").unwrap();
+        assert_eq!(caps.get(0).map(|m| m.range()), Some(0..6));
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(4..6)); // 最後の繰り返し
+
+        let regex = Regex::builder()
+            .captures(true)
+            .anchored_default(Anchored::No)
+            .build_from("(ab)+")
+            .unwrap();
+        let caps = regex.matches_with_captures_vm("xabab").unwrap();
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(3..5));
+    }
+
+    #[test]
+    fn captures_owned_outlives_haystack() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(ab*)-((c)|d)")
+            .unwrap();
+
+        let caps = {
+            let haystack = String::from("xabb-dy");
+            regex.captures_owned(&haystack).unwrap()
+        };
+
+        assert_eq!(caps.len(), 4);
+        assert_eq!(caps.get(0), Some("abb-d"));
+        assert_eq!(caps.get(1), Some("abb"));
+        assert_eq!(caps.get(2), Some("d"));
+        assert_eq!(caps.get(3), None);
+    }
+
+    #[test]
+    fn split_works() {
+        let regex = Regex::new(";").unwrap();
+
+        assert_eq!(regex.split("a;b;"), vec!["a", "b", ""]);
+        assert_eq!(regex.split_terminator("a;b;"), vec!["a", "b"]);
+        assert_eq!(regex.split_terminator("a;;b"), vec!["a", "", "b"]);
+        assert_eq!(regex.split_terminator(""), Vec::<&str>::new());
+
+        // str の同名のメソッドと同じ結果になる
+        for s in ["a;b;", ";", "a;b", ";;", ""] {
+            assert_eq!(regex.split(s), s.split(';').collect::<Vec<_>>());
+            assert_eq!(
+                regex.split_terminator(s),
+                s.split_terminator(';').collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn split_at_first_works() {
+        let regex = Regex::new("=").unwrap();
+
+        assert_eq!(regex.split_at_first("a=b=c"), Some(("a", "b=c")));
+        assert_eq!(regex.split_at_first("=b"), Some(("", "b")));
+        assert_eq!(regex.split_at_first("ab"), None);
+
+        // 区切りの前後の空白もまとめて取り除ける
+        let regex = Regex::new(" *: *").unwrap();
+        assert_eq!(regex.split_at_first("key :  value"), Some(("key", "value")));
+    }
+
+    #[test]
+    fn replace_all_swap() {
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(ab*) (cd*)")
+            .unwrap();
+
+        assert_eq!(regex.replace_all("abb cd, a c", "$2 $1"), "cd abb, c a");
+        assert_eq!(regex.replace_all("ab c", "[$0]"), "[ab c]");
+        assert_eq!(regex.replace_all("ab c", "${1}0"), "ab0");
+        assert_eq!(regex.replace_all("ab c", "$10"), ""); // グループ 10 は存在しない
+        assert_eq!(regex.replace_all("ab c", "$$1"), "$1");
+        assert_eq!(regex.replace_all("xyz", "$2 $1"), "xyz");
+    }
+
+    #[test]
+    fn replace_all_with_works() {
+        // `[a-z]` は無いため英小文字を列挙する
+        let lower: Vec<String> = ('a'..='z').map(String::from).collect();
+        let regex = Regex::new(&format!("({})+", lower.join("|"))).unwrap();
+
+        assert_eq!(
+            regex.replace_all_with("abc def", |m| m.as_str().to_uppercase()),
+            "ABC DEF"
+        );
+
+        // 一致の位置を使い、返した文字列の `$` はそのまま残す
+        let regex = Regex::new("b+").unwrap();
+        assert_eq!(
+            regex.replace_all_with("abbcb", |m| format!("${}", m.start())),
+            "a$1c$4"
+        );
+        assert_eq!(regex.replace_all_with("xyz", |_| unreachable!()), "xyz");
+    }
+
+    #[test]
+    fn replace_if_full_works() {
+        // `\d` は無いため数字を列挙する
+        let regex = Regex::new("(0|1|2|3|4|5|6|7|8|9)+").unwrap();
+
+        assert_eq!(regex.replace_if_full("123", "N"), Some("N".to_string()));
+        assert_eq!(regex.replace_if_full("12a", "N"), None);
+        assert_eq!(regex.replace_if_full("", "N"), None);
+
+        // 一部への一致を既定にしても、入力全体への一致のみを置き換える
+        let regex = Regex::builder()
+            .anchored_default(Anchored::No)
+            .captures(true)
+            .build_from("(ab*)-(c|d)")
+            .unwrap();
+
+        assert_eq!(
+            regex.replace_if_full("abb-d", "$2:$1"),
+            Some("d:abb".to_string())
+        );
+        assert_eq!(regex.replace_if_full("xabb-d", "$2:$1"), None);
+    }
+
+    #[test]
+    fn enumerate_finite_language() {
+        let regex = Regex::new("(a|b)(c|d)").unwrap();
+
+        assert!(regex.is_finite_language());
+        assert_eq!(
+            regex.enumerate(),
+            Some(vec![
+                "ac".to_string(),
+                "ad".to_string(),
+                "bc".to_string(),
+                "bd".to_string()
+            ])
+        );
+
+        let regex = Regex::new("ab?|c{0,2}").unwrap();
+        assert_eq!(
+            regex.enumerate(),
+            Some(["", "a", "ab", "c", "cc"].map(String::from).to_vec())
+        );
+
+        assert!(!Regex::new("a*").unwrap().is_finite_language());
+        assert_eq!(Regex::new("a*").unwrap().enumerate(), None);
+        assert!(!Regex::new("x(ab)+y").unwrap().is_finite_language());
+
+        // `.` を含むものは有限だが、列挙はしない
+        assert!(Regex::new("a.").unwrap().is_finite_language());
+        assert_eq!(Regex::new("a.").unwrap().enumerate(), None);
+
+        // 受理に関わらない閉路は無視する
+        assert_eq!(
+            Regex::new(r"a|b*\Ab").unwrap().enumerate(),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+    }
+
+    #[test]
+    fn live_prefixes_works() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+
+        assert_eq!(regex.live_prefixes(2), vec!["a", "ab", "ac"]);
+        assert_eq!(regex.live_prefixes(0), Vec::<String>::new());
+
+        // 受理しない途中の文字列も含み、`.` は列挙しない
+        let regex = Regex::new("xy.z|xw").unwrap();
+        assert_eq!(regex.live_prefixes(5), vec!["x", "xw", "xy"]);
+    }
+
+    #[test]
+    fn same_ast_works() {
+        let a_bc = Regex::new("a(b|c)").unwrap();
+        let ab_ac = Regex::new("ab|ac").unwrap();
+
+        assert!(a_bc.same_ast(&Regex::new("a(b|c)").unwrap()));
+        assert!(!a_bc.same_ast(&ab_ac));
+        assert!(!a_bc.same_ast(&Regex::new("a(?:b|c)").unwrap())); // グループの有無が異なる
+        assert!(a_bc.is_subset_of(&ab_ac) && ab_ac.is_subset_of(&a_bc));
+    }
+
+    #[test]
+    fn difference_works() {
+        let a_star = Regex::new("a*").unwrap();
+        let diff = a_star.difference(&Regex::new("a").unwrap());
+
+        for s in ["", "aa", "aaa"] {
+            assert!(diff.is_full_match(s), "{s:?}");
+        }
+        assert!(!diff.is_full_match("a"));
+        assert!(!diff.is_full_match("b"));
+        assert_eq!(
+            diff.minimal_dfa_signature(),
+            Regex::new("(aa+)?").unwrap().minimal_dfa_signature()
+        );
+
+        // 受理する文字列が無くなる場合は何にも一致しない
+        let empty = Regex::new("ab|a")
+            .unwrap()
+            .difference(&Regex::new("ab?").unwrap());
+        assert_eq!(empty.enumerate(), Some(vec![]));
+        assert!(!empty.is_match("ab"));
+
+        let words = Regex::new("(foo|bar|baz)(/(a|b)+)?").unwrap();
+        let diff = words.difference(&Regex::new("ba(r|z)/a+").unwrap());
+        assert!(diff.is_full_match("bar/ab"));
+        assert!(diff.is_full_match("foo/a"));
+        assert!(!diff.is_full_match("baz/aa"));
+        assert_eq!(diff.difference(&words).enumerate(), Some(vec![]));
+
+        // `a` 以外の任意の1文字はパターンで表せないが、一致は DFA で判定する
+        let not_a = Regex::new(".")
+            .unwrap()
+            .difference(&Regex::new("a").unwrap());
+        assert!(not_a.is_full_match("b") && not_a.is_full_match("あ"));
+        assert!(!not_a.is_full_match("a") && !not_a.is_full_match("bb"));
+        assert_eq!(not_a.find("aab").map(|m| m.range()), Some(2..3));
+        assert_eq!(
+            not_a
+                .find_iter("xay")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..1, 2..3]
+        );
+        assert!(not_a.is_subset_of(&Regex::new(".").unwrap()));
+        assert_eq!(not_a.match_len_bounds(), (1, Some(1)));
+
+        let any2 = Regex::new("..")
+            .unwrap()
+            .difference(&Regex::new(".").unwrap());
+        assert!(any2.is_full_match("xy") && !any2.is_full_match("x"));
+    }
+
+    #[test]
+    fn difference_apis_agree_with_dfa() {
+        use std::collections::BTreeSet;
+
+        use crate::{
+            explain::NonMatch,
+            matcher::{MatchStatus, PartialMatch},
+        };
+
+        // パターンで表せない DFA でも、入力を調べる機能はすべて DFA で判定する
+        let not_a = Regex::new(".")
+            .unwrap()
+            .difference(&Regex::new("a").unwrap());
+
+        assert_eq!(not_a.validate("b"), Ok(()));
+        assert_eq!(
+            not_a.explain_nonmatch("a"),
+            Some(NonMatch {
+                offset: 0,
+                expected: BTreeSet::new(),
+                expected_any: true,
+            })
+        );
+        assert_eq!(not_a.validate("bb").unwrap_err().offset(), 1);
+
+        assert_eq!(not_a.partial_match(""), PartialMatch::Partial);
+        assert_eq!(not_a.partial_match("b"), PartialMatch::Full);
+        assert_eq!(not_a.partial_match("a"), PartialMatch::None);
+        assert_eq!(not_a.partial_match("bb"), PartialMatch::None);
+
+        let mut matcher = not_a.matcher();
+        assert_eq!(matcher.status(), MatchStatus::Matching);
+        matcher.feed('x');
+        assert_eq!(matcher.status(), MatchStatus::Accepted);
+        matcher.feed('x');
+        assert_eq!(matcher.status(), MatchStatus::Dead);
+
+        assert!(not_a.lazy_dfa(4).matches("b") && !not_a.lazy_dfa(4).matches("a"));
+        assert_eq!(format!("{not_a:?}"), "Regex { dfa_states: 3 }");
+
+        // "a" の後は受理状態に到達しうる状態に遷移しない
+        let ab_not_b = Regex::new("ab|ac")
+            .unwrap()
+            .difference(&Regex::new("ab").unwrap());
+        assert_eq!(ab_not_b.partial_match("a"), PartialMatch::Partial);
+        assert_eq!(ab_not_b.partial_match("ab"), PartialMatch::None);
+        assert_eq!(
+            ab_not_b
+                .explain_nonmatch("ab")
+                .map(|n| (n.offset, n.expected)),
+            Some((1, BTreeSet::from(['c'])))
+        );
+
+        // パターンを求めないため、大きな DFA でもすぐに作れる
+        let long = Regex::new("a{2000}")
+            .unwrap()
+            .difference(&Regex::new("b").unwrap());
+        assert!(long.is_full_match(&"a".repeat(2000)));
+        assert_eq!(long.partial_match("aaa"), PartialMatch::Partial);
+    }
+
+    #[test]
+    fn minimal_dfa_signature_works() {
+        let signature = |re: &str| Regex::new(re).unwrap().minimal_dfa_signature();
+
+        assert_eq!(signature("ab|ac"), signature("a(b|c)"));
+        assert_ne!(signature("a*"), signature("a+"));
+        assert_eq!(signature("a+"), signature("aa*"));
+        assert_eq!(signature("(a|b)*"), signature("(a*b*)*"));
+
+        // アルファベットが異なっても言語が同じなら同じになる
+        assert_eq!(signature("a|."), signature("."));
+        assert_eq!(signature(r"a\Ab"), signature(r"x\Ay"));
+        assert_ne!(signature("."), signature("a"));
+    }
+
+    #[test]
+    fn shortest_example_works() {
+        let example = |re: &str| Regex::new(re).unwrap().shortest_example();
+
+        assert_eq!(example("a(b|c)*"), Some("a".to_string()));
+        assert_eq!(example("abc"), Some("abc".to_string()));
+        assert_eq!(example("(c|b)a|x*"), Some("".to_string()));
+        assert_eq!(example("cd|ab"), Some("ab".to_string()));
+        assert_eq!(example("a.b"), Some("aab".to_string()));
+        assert_eq!(example(r"a\Ab"), None); // \A は文字列の途中では成り立たない
+    }
+
+    #[test]
+    fn repeated_works() {
+        let regex = Regex::new("ab").unwrap().repeated(3);
+
+        assert!(regex.is_full_match("ababab"));
+        assert!(!regex.is_full_match("abab"));
+        assert!(!regex.is_full_match("abababab"));
+        assert!(!regex.is_full_match(""));
+
+        let regex = Regex::new("a|b").unwrap().repeated(2);
+        assert!(regex.is_full_match("ba"));
+        assert!(!regex.is_full_match("a"));
+
+        assert!(Regex::new("ab").unwrap().repeated(0).is_full_match(""));
+    }
+
+    #[test]
+    fn to_unanchored_and_to_anchored() {
+        let regex = Regex::new("bc").unwrap();
+        let unanchored = regex.to_unanchored();
+
+        for s in ["abcd", "bc", "abc", "bcd"] {
+            assert!(unanchored.is_full_match(s), "{s:?}");
+        }
+        assert!(!unanchored.is_full_match("acbd"));
+        assert!(!regex.is_full_match("abcd"));
+
+        let anchored = unanchored.to_anchored();
+        assert!(anchored.is_full_match("abcd"));
+
+        let anchored = regex.to_anchored();
+        assert!(anchored.is_full_match("bc"));
+        assert!(!anchored.is_match("abcd"));
+        assert!(regex.is_match("abcd"));
+        assert_eq!(anchored.find("bc").map(|m| m.range()), Some(0..2));
+    }
+
+    #[test]
+    fn common_prefix_with_works() {
+        let abc = Regex::new("abc(x)*").unwrap();
+        let abd = Regex::new("abd(y)*").unwrap();
+
+        assert_eq!(abc.required_prefix(), "abc");
+        assert_eq!(abc.common_prefix_with(&abd), "ab");
+        assert_eq!(abc.common_prefix_with(&Regex::new("x|y").unwrap()), "");
+    }
+
+    #[test]
+    fn matches_prefix_of_any_works() {
+        let regex = Regex::new("ab").unwrap();
+        assert_eq!(
+            regex.matches_prefix_of_any(&["abc", "xyz", "abx"]),
+            vec![0, 2]
+        );
+
+        let regex = Regex::new(r"a*\z").unwrap();
+        assert_eq!(
+            regex.matches_prefix_of_any(&["", "aa", "ab", "ba"]),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn matches_trimmed_works() {
+        let regex = Regex::new("(0|1|2|3|4|5|6|7|8|9)+").unwrap();
+
+        assert!(regex.matches_trimmed("  42  "));
+        assert!(regex.matches_trimmed("\t42\r\n"));
+        assert!(regex.matches_trimmed("\u{3000}42")); // 全角の空白
+        assert!(!regex.matches_trimmed("4 2"));
+        assert!(!regex.is_full_match(" 42"));
+
+        // 空白も一致させるパターンでも、取り除いた後の入力で判定する
+        assert!(!Regex::new(" a").unwrap().matches_trimmed(" a"));
+    }
+
+    #[test]
+    fn matches_symmetric_works() {
+        let regex = Regex::new("a(b|c)*a").unwrap();
+
+        assert!(regex.matches_symmetric("abcba"));
+        assert!(regex.matches_symmetric("abca")); // 逆順の acba も一致する
+
+        let regex = Regex::new("ab*c").unwrap();
+        assert!(!regex.matches_symmetric("abbc")); // 逆順の cbba は一致しない
+    }
+
+    #[cfg(feature = "internals")]
+    #[test]
+    fn trace_works() {
+        use crate::nfa::NfaTrans;
+
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let trace = regex.trace("abc");
+
+        assert_eq!(
+            trace.iter().map(|(at, _)| *at).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+
+        let accept = regex.nfa().accept();
+        assert!(!trace[0].1.contains(&accept));
+        assert!(
+            trace[1..]
+                .iter()
+                .all(|(_, states)| states.contains(&accept))
+        );
+        assert_ne!(trace[2].1, trace[3].1); // b と c の分岐の状態は異なる
+
+        for (before, after) in trace.iter().zip(trace[1..].iter()) {
+            let c = "abc"[before.0..after.0].chars().next().unwrap();
+            let states = before.1.iter().copied().collect();
+            let mut expected: Vec<_> = regex
+                .nfa()
+                .states_next(&states, &NfaTrans::Char(c))
+                .into_iter()
+                .collect();
+            expected.sort();
+
+            assert_eq!(after.1, expected);
+        }
+
+        let trace = regex.trace("ax");
+        assert_eq!(trace.len(), 3);
+        assert!(trace[2].1.is_empty());
+    }
+
+    #[test]
+    fn consume_prefix_works() {
+        let regex = Regex::new("a+").unwrap();
+
+        assert_eq!(regex.consume_prefix("aaab"), Some(3));
+        assert_eq!(regex.consume_prefix("baaa"), None);
+
+        let regex = Regex::new("(あ|a)*").unwrap();
+        assert_eq!(regex.consume_prefix("あaあb"), Some(7));
+        assert_eq!(regex.consume_prefix("b"), Some(0));
+    }
+
+    #[test]
+    fn match_prefix_len_works() {
+        let regex = Regex::new("a+").unwrap();
+
+        assert_eq!(regex.match_prefix_len("aaab"), Some(3));
+        assert_eq!(regex.match_prefix_len("baaa"), None);
+
+        // バイト長ではなく文字数を返す
+        let regex = Regex::new("(あ|a)*").unwrap();
+        assert_eq!(regex.match_prefix_len("あaあb"), Some(3));
+        assert_eq!(regex.consume_prefix("あaあb"), Some(7));
+    }
+
+    #[test]
+    fn text_anchors() {
+        let regex = Regex::new(r"\Aabc\z").unwrap();
+
+        assert!(regex.is_full_match("abc"));
+        assert!(!regex.is_full_match("abcc"));
+        assert_eq!(regex.find("abc").map(|m| m.range()), Some(0..3));
+        assert_eq!(regex.find("xabc").map(|m| m.range()), None); // 先頭でない
+        assert_eq!(regex.find("abcx").map(|m| m.range()), None); // 終端でない
+
+        let regex = Regex::new(r"b\z").unwrap();
+        assert_eq!(regex.find("bab").map(|m| m.range()), Some(2..3));
+
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from(r"(\Aa|b)*")
+            .unwrap();
+        assert!(regex.is_full_match("abb"));
+        assert!(!regex.is_full_match("ba")); // 2文字目は先頭でない
+        assert_eq!(
+            regex.captures("ab").unwrap().get(1).map(|m| m.range()),
+            Some(1..2)
+        );
+
+        let regex = Regex::new(r"a\Ab").unwrap();
+        assert!(!regex.is_full_match("ab")); // \A は文字列の途中では成り立たない
+
+        let regex = Regex::new(r"\*\(").unwrap();
+        assert!(regex.is_full_match("*(")); // エスケープされたメタ文字
+    }
+
+    #[test]
+    fn regex_metachars() {
+        let regex = Regex::new("a(b|c)+d?").unwrap();
+
+        assert!(regex.is_full_match("ab"));
+        assert!(regex.is_full_match("acbd"));
+        assert!(!regex.is_full_match("a")); // (b|c) が1回以上必要
+        assert!(!regex.is_full_match("abdd")); // d は高々1回
+
+        let regex = Regex::new("a.c").unwrap();
+
+        assert!(regex.is_full_match("abc"));
+        assert!(regex.is_full_match("a.c"));
+        assert!(regex.is_full_match("aあc"));
+        assert!(!regex.is_full_match("ac"));
+
+        let regex = Regex::new(r"a\.c").unwrap();
+        assert!(regex.is_full_match("a.c"));
+        assert!(!regex.is_full_match("abc"));
+
+        let regex = Regex::new("^ab$").unwrap();
+        assert_eq!(regex.find("ab").map(|m| m.range()), Some(0..2));
+        assert_eq!(regex.find("cab").map(|m| m.range()), None);
+
+        assert!(Regex::new("{2}").is_err()); // 繰り返す対象が無い
+        assert!(Regex::new("[ab]").is_err()); // 未対応
+    }
+
+    #[test]
+    fn bounded_repetition() {
+        let regex = Regex::new("a(bc){2,3}d{2}").unwrap();
+
+        assert!(regex.is_full_match("abcbcdd"));
+        assert!(regex.is_full_match("abcbcbcdd"));
+        assert!(!regex.is_full_match("abcdd"));
+        assert!(!regex.is_full_match("abcbcbcbcdd"));
+        assert!(!regex.is_full_match("abcbcd"));
+
+        let regex = Regex::new("a{2,}|b{0}").unwrap();
+        assert!(regex.is_full_match("aa"));
+        assert!(regex.is_full_match("aaaaa"));
+        assert!(regex.is_full_match(""));
+        assert!(!regex.is_full_match("a"));
+        assert!(!regex.is_full_match("b"));
+
+        // グループは展開しても1つのまま
+        let regex = Regex::builder()
+            .captures(true)
+            .build_from("(a){3}(b)")
+            .unwrap();
+        assert_eq!(regex.captures("aaab").unwrap().len(), 3);
+        assert_eq!(
+            regex.captures("aaab").unwrap().get(2).map(|m| m.range()),
+            Some(3..4)
+        );
+
+        assert!(matches!(
+            Regex::new("a{3,2}"),
+            Err(RegexParseError::InvalidRepetition(3, 2))
+        ));
+        assert!(Regex::new("a{,2}").is_err());
+        assert!(Regex::new("a{2").is_err());
+        assert!(matches!(
+            Regex::new("a{99999999999}"),
+            Err(RegexParseError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn size_limit() {
+        assert!(matches!(
+            Regex::new("a{100000}"),
+            Err(RegexParseError::TooLarge(100_000))
+        ));
+        assert!(matches!(
+            Regex::builder().size_limit(1000).build_from("(a{100}){20}"),
+            Err(RegexParseError::TooLarge(1000))
+        ));
+        // 上限に関わらず、展開の途中で大きくなりすぎるものは拒否する
+        assert!(matches!(
+            Regex::builder()
+                .size_limit(usize::MAX)
+                .build_from("((a{1000}){1000}){1000}"),
+            Err(RegexParseError::TooLarge(_))
+        ));
+
+        let regex = Regex::builder()
+            .size_limit(2000)
+            .build_from("a{1000}")
+            .unwrap();
+        assert!(regex.is_full_match(&"a".repeat(1000)));
+        assert!(!regex.is_full_match(&"a".repeat(999)));
+    }
+
+    #[test]
+    fn captures_opt_in() {
+        let plain = Regex::new("(ab*)-(?:c|d)").unwrap();
+        let capturing = Regex::builder()
+            .captures(true)
+            .build_from("(ab*)-(?:c|d)")
+            .unwrap();
+
+        for s in ["abb-c", "a-d", "ab-", "b-c"] {
+            assert_eq!(plain.is_full_match(s), capturing.is_full_match(s));
+        }
+
+        let caps = plain.captures("xabb-cy").unwrap();
+        assert_eq!(caps.len(), 1);
+        assert_eq!(caps.get(0).map(|m| m.range()), Some(1..6));
+
+        // `(?:...)` はビルダーの指定に関わらずグループにならない
+        let caps = capturing.captures("xabb-cy").unwrap();
+        assert_eq!(caps.len(), 2);
+        assert_eq!(caps.get(1).map(|m| m.range()), Some(1..4));
+
+        assert!(matches!(
+            Regex::new("(?a)"),
+            Err(RegexParseError::UnexpectedToken(TokenKind::Char('a'), _))
+        ));
+    }
+
+    #[test]
+    fn new_never_panics() {
+        const ALPHABET: &[char] = &[
+            'a', 'b', '(', ')', '|', '*', '+', '?', '.', '[', ']', '{', '}', '^', '$', '\\', ':',
+        ];
+
+        // 固定の種による線形合同法で、メタ文字が多く並ぶパターンを列挙する
+        let mut seed: u32 = 1;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+
+            (seed >> 16) as usize
+        };
+
+        for _ in 0..2000 {
+            let len = next() % 12;
+            let pattern: String = (0..len)
+                .map(|_| ALPHABET[next() % ALPHABET.len()])
+                .collect();
+
+            if let Ok(regex) = Regex::new(&pattern) {
+                regex.is_full_match("ab(");
+                regex.find("ba");
+            }
+        }
+
+        let extreme = [
+            "(".repeat(100_000),
+            ")".repeat(100_000),
+            "(a)".repeat(10_000),
+            "a|".repeat(10_000),
+            format!("{}a{}", "(".repeat(256), ")".repeat(256)),
+            "\\".to_string(),
+            "(?".to_string(),
+        ];
+
+        for pattern in extreme.iter() {
+            let _ = Regex::new(pattern);
+        }
+
+        assert!(matches!(
+            Regex::new(&format!("{}a{}", "(".repeat(257), ")".repeat(257))),
+            Err(RegexParseError::TooDeeplyNested(256))
+        ));
+    }
+
+    #[test]
+    fn longest_match_info_works() {
+        let regex = Regex::new("a+").unwrap();
+        assert_eq!(regex.longest_match_info("aaa"), (Some(3), true));
+        assert_eq!(regex.longest_match_info("aab"), (Some(2), false));
+        assert_eq!(regex.longest_match_info(""), (None, true));
+
+        // これ以上読めない場合は偽
+        let regex = Regex::new("ab|abcd").unwrap();
+        assert_eq!(regex.longest_match_info("ab"), (Some(2), true));
+        assert_eq!(regex.longest_match_info("abc"), (Some(2), true));
+        assert_eq!(regex.longest_match_info("abcd"), (Some(4), false));
+
+        // `\z` は入力の終端でのみ通過する
+        let regex = Regex::new(r"a\z|ab").unwrap();
+        assert_eq!(regex.longest_match_info("a"), (Some(1), true));
+        assert_eq!(regex.longest_match_info("ab"), (Some(2), false));
+    }
+
+    #[test]
+    fn matches_try_chars_works() {
+        let regex = Regex::new("a(b|c)*").unwrap();
+        let ok = |s: &str| s.chars().map(Ok::<char, &str>).collect::<Vec<_>>();
+
+        assert_eq!(regex.matches_try_chars(ok("abcb")), Ok(true));
+        assert_eq!(regex.matches_try_chars(ok("abd")), Ok(false));
+        assert_eq!(regex.matches_try_chars(ok("")), Ok(false));
+        assert_eq!(
+            Regex::new(r"\A").unwrap().matches_try_chars(ok("")),
+            Ok(true)
+        );
+
+        // 途中のエラーを返す
+        let chars = vec![Ok('a'), Ok('b'), Err("invalid byte"), Ok('c')];
+        assert_eq!(regex.matches_try_chars(chars), Err("invalid byte"));
+
+        // UTF-16 のデコーダをそのまま渡せる
+        let units: Vec<u16> = "acb".encode_utf16().chain([0xD800]).collect();
+        assert!(regex.matches_try_chars(char::decode_utf16(units)).is_err());
+        let units: Vec<u16> = "acb".encode_utf16().collect();
+        assert_eq!(regex.matches_try_chars(char::decode_utf16(units)), Ok(true));
+
+        // 一致しないことが確定した後のエラーは読まない
+        let chars = vec![Ok('x'), Err("invalid byte")];
+        assert_eq!(regex.matches_try_chars(chars), Ok(false));
+    }
+
+    #[test]
+    fn try_compile_all_works() {
+        let results = Regex::try_compile_all(&["a+", "(a", "b|c", "a{3,2}", ""]);
+
+        assert_eq!(results.len(), 5);
+        assert!(results[0].as_ref().is_ok_and(|r| r.is_full_match("aa")));
+        assert!(matches!(results[1], Err(RegexParseError::UnexpectedEOF)));
+        assert!(results[2].as_ref().is_ok_and(|r| r.is_full_match("c")));
+        assert!(matches!(
+            results[3],
+            Err(RegexParseError::InvalidRepetition(3, 2))
+        ));
+        assert!(results[4].is_err());
+
+        assert!(Regex::try_compile_all(&[]).is_empty());
+    }
+
+    #[test]
+    fn char_escapes() {
+        assert!(Regex::new(r"a\tb").unwrap().is_full_match("a\tb"));
+        assert!(Regex::new(r"\u{41}").unwrap().is_full_match("A"));
+        assert!(
+            Regex::new(r"\x41\u{1F600}+")
+                .unwrap()
+                .is_full_match("A😀😀")
+        );
+        assert!(Regex::new(r"\r\n").unwrap().is_match("line\r\n"));
 
-        assert!(regex.matches("ad")); // bc が0回
-        assert!(regex.matches("abcd")); // bc 1回
-        assert!(regex.matches("abcbcd")); // bc 2回
-        assert!(regex.matches("abcbcbcd")); // bc 3回
-        assert!(!regex.matches("a")); // d が無い
-        assert!(!regex.matches("d")); // 先頭が a でない
-        assert!(!regex.matches("abc")); // 末尾が d でない
-        assert!(!regex.matches("abcbd")); // bc の途中壊れ
-        assert!(!regex.matches("aabcbcd")); // 最初に a が2つ
-        assert!(!regex.matches("abcbcccd")); // bc の途中に c が余分
+        let err = Regex::new(r"\u{110000}").unwrap_err();
+        assert!(matches!(err, RegexParseError::InvalidCodePoint(0x110000)));
+        assert_eq!(
+            err.to_string(),
+            "failed to parse regex, `0x110000` is not a Unicode scalar value"
+        );
+        assert!(matches!(
+            Regex::new(r"\xZZ"),
+            Err(RegexParseError::InvalidEscape(_))
+        ));
     }
 
     // #[test]
@@ -0,0 +1,173 @@
+use crate::{
+    captures::Match,
+    nfa::{Nfa, NfaTrans},
+    parser::{Look, Node},
+    regex::Regex,
+};
+
+impl Node {
+    // 文字列を逆順にしたものに一致する AST を返す。`\A` と `\z` も入れ替える
+    pub(crate) fn reversed(&self) -> Node {
+        match self {
+            Self::Look(Look::StartText) => Self::Look(Look::EndText),
+            Self::Look(Look::EndText) => Self::Look(Look::StartText),
+            Self::Empty | Self::Char(_) | Self::Any => self.clone(),
+            Self::Concat(items) => Self::Concat(items.iter().rev().map(Self::reversed).collect()),
+            Self::Alt(branches) => Self::Alt(branches.iter().map(Self::reversed).collect()),
+            Self::Repeat(n) => Self::Repeat(Box::new(n.reversed())),
+            Self::Plus(n) => Self::Plus(Box::new(n.reversed())),
+            Self::Optional(n) => Self::Optional(Box::new(n.reversed())),
+            Self::Group(n, name) => Self::Group(Box::new(n.reversed()), name.clone()),
+        }
+    }
+}
+
+// end で終わる一致のうち最も左から始まるものの開始位置を返す
+fn longest_match_ending_at(regex: &Regex, haystack: &str, end: usize) -> Option<usize> {
+    match regex.backend_dfa() {
+        // DFA は逆順に読めないため、左の開始位置から順に試す
+        Some(dfa) => haystack[..end]
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(end))
+            .find(|start| dfa.matches(&haystack[*start..end])),
+        None => longest_match_ending_at_reversed(regex.reversed_nfa(), haystack, end),
+    }
+}
+
+// 逆順の NFA で、end で終わる一致のうち最も左から始まるものの開始位置を返す
+// 逆順の入力では haystack の終端が先頭、haystack の先頭が終端になる
+fn longest_match_ending_at_reversed(nfa: &Nfa, haystack: &str, end: usize) -> Option<usize> {
+    let mut states = nfa.start_states(end == haystack.len());
+
+    let mut start = nfa
+        .is_accepting(&states, end == haystack.len(), end == 0)
+        .then_some(end);
+
+    for (i, c) in haystack[..end].char_indices().rev() {
+        states = nfa.states_next(&states, &NfaTrans::Char(c));
+
+        if states.is_empty() {
+            break;
+        }
+
+        if nfa.is_accepting(&states, false, i == 0) {
+            start = Some(i);
+        }
+    }
+
+    start
+}
+
+impl Regex {
+    /// 入力の終端で終わる部分 (接尾辞) がパターンに一致するかを返す (`(?:pattern)\z` での `is_match` に相当する)
+    /// 逆順にしたパターンの NFA で、入力を終端から先頭へ一度だけ読んで判定する
+    pub fn matches_anchored_end(&self, haystack: &str) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        let haystack = &*self.normalize(haystack);
+        longest_match_ending_at(self, haystack, haystack.len()).is_some()
+    }
+
+    /// 重ならない一致の範囲を右から順に返す。範囲は `find_iter` と同じく元の文字列でのバイトオフセットである
+    ///
+    /// 入力の終端から左へ、最も右で終わり、その中で最も左から始まる (最も長い) 一致を探す
+    /// 空でない一致の後は、その開始位置で終わる一致を探す (そこで空の一致が見つかることもある)
+    /// 空の一致の後は、その位置の前の文字の境界で終わる一致を探し、入力の先頭での空の一致の後は終了する
+    /// そのため一致の区切り方は `find_iter` の結果を逆順にしたものとは異なることがある
+    pub fn matches_reverse_iter<'r, 'h>(
+        &'r self,
+        haystack: &'h str,
+    ) -> impl Iterator<Item = Match<'h>> + use<'r, 'h> {
+        self.assert_normalized(haystack);
+        let mut end = Some(haystack.len());
+
+        std::iter::from_fn(move || {
+            let mut at = end?;
+
+            let start = loop {
+                if let Some(start) = longest_match_ending_at(self, haystack, at) {
+                    break start;
+                }
+
+                // この位置で終わる一致が無ければ1文字前に戻る
+                at -= haystack[..at].chars().next_back()?.len_utf8();
+            };
+
+            end = if start == at {
+                haystack[..start]
+                    .chars()
+                    .next_back()
+                    .map(|c| start - c.len_utf8())
+            } else {
+                Some(start)
+            };
+
+            Some(Match::new(haystack, start..at))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn matches_anchored_end_works() {
+        let regex = Regex::new("bc").unwrap();
+
+        assert!(regex.matches_anchored_end("abc"));
+        assert!(regex.matches_anchored_end("bc"));
+        assert!(!regex.matches_anchored_end("bcd"));
+        assert!(!regex.matches_anchored_end(""));
+
+        // `\A` がある場合は入力全体への一致になる
+        let regex = Regex::new(r"\Ab+").unwrap();
+        assert!(regex.matches_anchored_end("bb"));
+        assert!(!regex.matches_anchored_end("abb"));
+
+        // 空文字列に一致するパターンは常に一致する
+        assert!(Regex::new("x*").unwrap().matches_anchored_end("abc"));
+    }
+
+    #[test]
+    fn matches_reverse_iter_works() {
+        let regex = Regex::new("a+").unwrap();
+        assert_eq!(
+            regex
+                .matches_reverse_iter("aa b aaa")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![5..8, 0..2]
+        );
+
+        // 拡張子のような末尾の区切りを右から取り出す
+        let regex = Regex::new(r"\.(a|g|r|t|z)+").unwrap();
+        assert_eq!(
+            regex
+                .matches_reverse_iter("x.tar.gz")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![5..8, 1..5]
+        );
+
+        // `\A` / `\z` は逆向きでも文字列の先頭・終端を表す
+        let regex = Regex::new(r"\Aa|b\z").unwrap();
+        assert_eq!(
+            regex
+                .matches_reverse_iter("aab")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![2..3, 0..1]
+        );
+
+        // 空の一致の後は1文字前に戻り、先頭での空の一致で終了する
+        let regex = Regex::new("a*").unwrap();
+        assert_eq!(
+            regex
+                .matches_reverse_iter("bあa")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![4..5, 4..4, 1..1, 0..0]
+        );
+    }
+}
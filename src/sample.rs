@@ -0,0 +1,123 @@
+use std::collections::BTreeSet;
+
+use crate::{nfa::NfaTrans, regex::Regex};
+
+/// `Regex::sample_uniform` が使う乱数の生成器
+///
+/// このクレートは外部のクレートに依存しないため、`rand` クレートの生成器などはこのトレイトを実装して渡す
+pub trait Rng {
+    /// 一様な32ビットの乱数を返す
+    fn next_u32(&mut self) -> u32;
+}
+
+// 0 以上 n 未満の乱数を返す (n が大きくない限り偏りは無視できる)
+fn below<R: Rng>(rng: &mut R, n: usize) -> usize {
+    (rng.next_u32() as usize) % n
+}
+
+// `.` に対して生成する文字 (表示できる ASCII 文字の ` ` から `~` まで)
+const ANY_FIRST: u8 = b' ';
+const ANY_COUNT: usize = 95;
+
+impl Regex {
+    /// パターン全体に一致する長さ `max_len` 文字以下の文字列を、NFA をランダムに辿って1つ生成する
+    ///
+    /// 各位置では次に読める文字から一様に選び、受理できる位置では 1/2 の確率で終了する
+    /// そのため一致する文字列全体からの一様な分布ではなく、短い文字列ほど選ばれやすい
+    /// `max_len` 文字までに受理できなかった場合や、選んだ文字の先で一致できなくなった場合は None を返す
+    /// `.` には表示できる ASCII 文字のいずれかを生成する
+    pub fn sample_uniform<R: Rng>(&self, rng: &mut R, max_len: usize) -> Option<String> {
+        let nfa = self.nfa();
+        let mut states = nfa.start_states(true);
+        let mut sampled = String::new();
+
+        for len in 0..=max_len {
+            if nfa.is_accepting(&states, len == 0, true) && (len == max_len || below(rng, 2) == 0) {
+                return Some(sampled);
+            }
+
+            if len == max_len {
+                break;
+            }
+
+            let mut chars = BTreeSet::new();
+            let mut any = false;
+
+            for transs in states.iter().filter_map(|s| nfa.states().get(s)) {
+                for t in transs.keys() {
+                    match t {
+                        NfaTrans::Char(c) => {
+                            chars.insert(*c);
+                        }
+                        NfaTrans::Any => any = true,
+                        NfaTrans::Epsilon | NfaTrans::Look(_) => {}
+                    }
+                }
+            }
+
+            // `.` は読める文字の1つとして数える
+            let choices = chars.len() + usize::from(any);
+            if choices == 0 {
+                return None;
+            }
+
+            let i = below(rng, choices);
+            let c = match chars.iter().nth(i) {
+                Some(c) => *c,
+                None => char::from(ANY_FIRST + below(rng, ANY_COUNT) as u8),
+            };
+
+            states = nfa.states_next(&states, &NfaTrans::Char(c));
+            sampled.push(c);
+
+            if states.is_empty() {
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{regex::Regex, sample::Rng};
+
+    // 再現性のため固定のシードから始める xorshift
+    struct XorShift(u32);
+
+    impl Rng for XorShift {
+        fn next_u32(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+
+            self.0
+        }
+    }
+
+    #[test]
+    fn sampled_strings_match() {
+        let mut rng = XorShift(2463534242);
+
+        let regex = Regex::new("a(b|c)*").unwrap();
+        for _ in 0..100 {
+            let sampled = regex.sample_uniform(&mut rng, 10).unwrap();
+
+            assert!(regex.is_full_match(&sampled), "{sampled:?}");
+            assert!(sampled.chars().count() <= 10);
+        }
+
+        let regex = Regex::new(r"(x|.y)+\z").unwrap();
+        for _ in 0..100 {
+            if let Some(sampled) = regex.sample_uniform(&mut rng, 6) {
+                assert!(regex.is_full_match(&sampled), "{sampled:?}");
+            }
+        }
+
+        // 長さの上限までに受理できない場合は None
+        let regex = Regex::new("abc").unwrap();
+        assert_eq!(regex.sample_uniform(&mut rng, 2), None);
+        assert_eq!(regex.sample_uniform(&mut rng, 3), Some("abc".to_string()));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    builder::{Anchored, MatchKind},
+    captures::Match,
+    regex::Regex,
+};
+
+/// `Regex::search` の探索方法の指定
+///
+/// 既定では `Regex::find` と同じく、入力の先頭から最も左の最長の一致を探す
+/// ビルダーでの `anchored_default` や `match_kind` の指定には影響されない
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    anchored: Anchored,
+    start: usize,
+    match_kind: MatchKind,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            anchored: Anchored::No,
+            start: 0,
+            match_kind: MatchKind::LeftmostLongest,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `Anchored::Yes` の場合、探索の開始位置から入力の終端までの全体に一致するかのみを判定する
+    pub fn anchored(mut self, anchored: Anchored) -> Self {
+        self.anchored = anchored;
+
+        self
+    }
+
+    /// 探索を始めるバイトオフセット (`Regex::find_at` と同じく、`\A` は常に文字列の先頭を表す)
+    pub fn start(mut self, start: usize) -> Self {
+        self.start = start;
+
+        self
+    }
+
+    pub fn match_kind(mut self, kind: MatchKind) -> Self {
+        self.match_kind = kind;
+
+        self
+    }
+}
+
+impl Regex {
+    /// `opts` の指定に従って一致を探し、その範囲を返す
+    /// 開始位置が文字の境界でない、または入力の長さを超える場合は panic する
+    pub fn search<'h>(&self, haystack: &'h str, opts: SearchOptions) -> Option<Match<'h>> {
+        self.assert_normalized(haystack);
+        let range = match opts.anchored {
+            Anchored::Yes => {
+                let end = self.longest_match_at(haystack, opts.start)?;
+
+                (end == haystack.len()).then_some(opts.start..end)
+            }
+            Anchored::No => self.find_at_with(haystack, opts.start, opts.match_kind),
+        }?;
+
+        Some(Match::new(haystack, range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::{Anchored, MatchKind},
+        regex::Regex,
+        search::SearchOptions,
+    };
+
+    #[test]
+    fn search_options() {
+        let regex = Regex::new("a|ab").unwrap();
+        let haystack = "xabab";
+        let opts = SearchOptions::new();
+
+        assert_eq!(regex.search(haystack, opts).map(|m| m.range()), Some(1..3));
+        assert_eq!(
+            regex.search(haystack, opts.start(2)).map(|m| m.range()),
+            Some(3..5)
+        );
+        assert_eq!(
+            regex
+                .search(haystack, opts.match_kind(MatchKind::LeftmostFirst))
+                .map(|m| m.range()),
+            Some(1..2)
+        );
+        assert_eq!(
+            regex
+                .search(haystack, opts.start(2).match_kind(MatchKind::LeftmostFirst))
+                .map(|m| m.range()),
+            Some(3..4)
+        );
+
+        // 開始位置から終端までの全体への一致
+        assert_eq!(
+            regex
+                .search(haystack, opts.anchored(Anchored::Yes))
+                .map(|m| m.range()),
+            None
+        );
+        assert_eq!(
+            regex
+                .search(haystack, opts.anchored(Anchored::Yes).start(3))
+                .map(|m| m.range()),
+            Some(3..5)
+        );
+        assert_eq!(
+            regex
+                .search(haystack, opts.anchored(Anchored::Yes).start(1))
+                .map(|m| m.range()),
+            None
+        );
+
+        // ビルダーの指定には影響されない
+        let regex = Regex::builder()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build_from("a|ab")
+            .unwrap();
+        assert_eq!(regex.search(haystack, opts).map(|m| m.range()), Some(1..3));
+        assert_eq!(regex.find(haystack).map(|m| m.range()), Some(1..2));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    nfa::{Nfa, NfaTrans, Symbol},
+    regex::Regex,
+};
+
+/// 文字の代わりに任意の記号の列に対して判定する正規表現
+/// `Regex::map_symbols` でパターンの各文字を記号に置き換えて作る
+#[derive(Debug, Clone)]
+pub struct SymbolRegex<S> {
+    nfa: Nfa<S>,
+}
+
+impl<S: Symbol> SymbolRegex<S> {
+    /// 記号の列全体がパターンに一致するかを返す。`.` は任意の1つの記号に一致する
+    pub fn matches(&self, input: &[S]) -> bool {
+        let mut states = self.nfa.start_states(true);
+
+        for s in input {
+            states = self.nfa.states_next(&states, &NfaTrans::Char(s.clone()));
+
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        self.nfa.is_accepting(&states, input.is_empty(), true)
+    }
+}
+
+impl Regex {
+    /// パターン中の各文字を `f` で記号に置き換えた `SymbolRegex` を返す
+    /// DFA から作った正規表現 (`difference` など) では DFA から求めたパターンを使い、パターンで表せない場合は panic する
+    pub fn map_symbols<S: Symbol>(&self, f: impl Fn(char) -> S) -> SymbolRegex<S> {
+        SymbolRegex {
+            nfa: self.nfa().map_symbols(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Token {
+        Ident,
+        Comma,
+        Other(char),
+    }
+
+    #[test]
+    fn match_integer_symbols() {
+        let regex = Regex::new("a(b|c)*").unwrap().map_symbols(|c| c as u32);
+
+        assert!(regex.matches(&[97]));
+        assert!(regex.matches(&[97, 98, 99, 98]));
+        assert!(!regex.matches(&[]));
+        assert!(!regex.matches(&[98]));
+        assert!(!regex.matches(&[97, 100]));
+    }
+
+    #[test]
+    fn match_token_symbols() {
+        // i は識別子、`,` は区切りを表す
+        let regex = Regex::new("i(,i)*,?.?").unwrap().map_symbols(|c| match c {
+            'i' => Token::Ident,
+            ',' => Token::Comma,
+            c => Token::Other(c),
+        });
+
+        assert!(regex.matches(&[Token::Ident, Token::Comma, Token::Ident]));
+        assert!(regex.matches(&[Token::Ident, Token::Comma, Token::Other(';')]));
+        assert!(!regex.matches(&[Token::Comma, Token::Ident]));
+    }
+}